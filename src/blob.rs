@@ -310,6 +310,14 @@ impl Data {
         }
     }
 
+    /// Returns `Error::InvalidOwners` if the data is private and owned by a zero key.
+    pub fn validate_owner(&self) -> Result<(), Error> {
+        match self {
+            Data::Private(data) if data.owner().is_zero() => Err(Error::InvalidOwners),
+            Data::Private(_) | Data::Public(_) => Ok(()),
+        }
+    }
+
     /// Returns size of this data after serialisation.
     pub fn serialised_size(&self) -> u64 {
         match self {
@@ -7,6 +7,7 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+use crate::{Money, MIN_TRANSFER};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
@@ -86,7 +87,12 @@ pub enum Error {
     /// Transfer ID already exists.
     TransferIdExists,
     /// Insufficient money.
-    InsufficientBalance,
+    InsufficientBalance {
+        /// The balance available.
+        balance: Money,
+        /// The amount required.
+        required: Money,
+    },
     /// Inexistent balance.
     NoSuchBalance,
     /// Inexistent sender balance.
@@ -97,6 +103,85 @@ pub enum Error {
     BalanceExists,
     /// Expected data size exceeded.
     ExceededSize,
+    /// The message was relayed by more than `MAX_PROXIES` proxies.
+    TooManyProxies,
+    /// The transfer amount is below `MIN_TRANSFER`.
+    AmountBelowMinimum,
+}
+
+impl Error {
+    /// Returns `true` if the same request may succeed when retried later, e.g. after a failure
+    /// at Vault level. Errors about the request itself (access denied, missing data, invalid
+    /// signature etc.) are permanent.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            Error::NetworkOther(_) => true,
+            Error::AccessDenied
+            | Error::NoSuchLoginPacket
+            | Error::LoginPacketExists
+            | Error::NoSuchData
+            | Error::DataExists
+            | Error::NoSuchEntry
+            | Error::TooManyEntries
+            | Error::InvalidEntryActions(_)
+            | Error::NoSuchKey
+            | Error::DuplicateEntryKeys
+            | Error::InvalidOwners
+            | Error::InvalidSuccessor(_)
+            | Error::InvalidOwnersSuccessor(_)
+            | Error::InvalidPermissionsSuccessor(_)
+            | Error::InvalidOperation
+            | Error::SigningKeyTypeMismatch
+            | Error::InvalidSignature
+            | Error::DuplicateMessageId
+            | Error::LossOfPrecision
+            | Error::ExcessiveValue
+            | Error::FailedToParse(_)
+            | Error::TransferIdExists
+            | Error::InsufficientBalance { .. }
+            | Error::NoSuchBalance
+            | Error::NoSuchSender
+            | Error::NoSuchRecipient
+            | Error::BalanceExists
+            | Error::ExceededSize
+            | Error::TooManyProxies
+            | Error::AmountBelowMinimum => false,
+        }
+    }
+
+    /// Returns the HTTP status code best describing the error, e.g. for a REST gateway.
+    pub fn http_status(&self) -> u16 {
+        match *self {
+            Error::InvalidEntryActions(_)
+            | Error::DuplicateEntryKeys
+            | Error::InvalidOwners
+            | Error::InvalidOperation
+            | Error::LossOfPrecision
+            | Error::ExcessiveValue
+            | Error::FailedToParse(_)
+            | Error::AmountBelowMinimum => 400,
+            Error::SigningKeyTypeMismatch | Error::InvalidSignature => 401,
+            Error::InsufficientBalance { .. } => 402,
+            Error::AccessDenied => 403,
+            Error::NoSuchLoginPacket
+            | Error::NoSuchData
+            | Error::NoSuchEntry
+            | Error::NoSuchKey
+            | Error::NoSuchBalance
+            | Error::NoSuchSender
+            | Error::NoSuchRecipient => 404,
+            Error::LoginPacketExists
+            | Error::DataExists
+            | Error::InvalidSuccessor(_)
+            | Error::InvalidOwnersSuccessor(_)
+            | Error::InvalidPermissionsSuccessor(_)
+            | Error::DuplicateMessageId
+            | Error::TransferIdExists
+            | Error::BalanceExists => 409,
+            Error::TooManyEntries | Error::ExceededSize | Error::TooManyProxies => 413,
+            Error::NetworkOther(_) => 503,
+        }
+    }
 }
 
 impl<T: Into<String>> From<T> for Error {
@@ -149,13 +234,23 @@ impl Display for Error {
                 write!(f, "Failed to parse from a string: {}", error)
             }
             Error::TransferIdExists => write!(f, "Transfer with a given ID already exists"),
-            Error::InsufficientBalance => write!(f, "Not enough money to complete this operation"),
+            Error::InsufficientBalance { balance, required } => write!(
+                f,
+                "Not enough money to complete this operation: {} required, but only {} available",
+                required, balance
+            ),
             Error::NoSuchBalance => write!(f, "Balance does not exist"),
             Error::NoSuchSender => write!(f, "Sender does not exist"),
             Error::NoSuchRecipient => write!(f, "Recipient does not exist"),
             Error::BalanceExists => write!(f, "Balance already exists"),
             Error::DuplicateMessageId => write!(f, "MessageId already exists"),
             Error::ExceededSize => write!(f, "Size of the structure exceeds the limit"),
+            Error::TooManyProxies => write!(f, "Message was relayed by too many proxies"),
+            Error::AmountBelowMinimum => write!(
+                f,
+                "Transfer amount is below the minimum of {}",
+                MIN_TRANSFER
+            ),
         }
     }
 }
@@ -187,13 +282,15 @@ impl error::Error for Error {
             }
             Error::FailedToParse(_) => "Failed to parse entity",
             Error::TransferIdExists => "Transfer with a given ID already exists",
-            Error::InsufficientBalance => "Not enough money to complete this operation",
+            Error::InsufficientBalance { .. } => "Not enough money to complete this operation",
             Error::NoSuchBalance => "Balance does not exist",
             Error::NoSuchSender => "Sender does not exist",
             Error::NoSuchRecipient => "Recipient does not exist",
             Error::BalanceExists => "Balance already exists",
             Error::DuplicateMessageId => "MessageId already exists",
             Error::ExceededSize => "Exceeded the size limit",
+            Error::TooManyProxies => "Too many proxies",
+            Error::AmountBelowMinimum => "Transfer amount is below the minimum",
         }
     }
 }
@@ -208,3 +305,24 @@ pub enum EntryError {
     /// Invalid version when updating an entry. Contains the current entry Key.
     InvalidSuccessor(u8),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_errors() {
+        assert!(Error::NetworkOther("database failure".to_string()).is_transient());
+        assert!(Error::from("serialisation failure").is_transient());
+
+        assert!(!Error::AccessDenied.is_transient());
+        assert!(!Error::NoSuchData.is_transient());
+        assert!(!Error::InvalidSignature.is_transient());
+        assert!(!Error::InvalidSuccessor(0).is_transient());
+        assert!(!Error::InsufficientBalance {
+            balance: Money::zero(),
+            required: Money::from_nano(1),
+        }
+        .is_transient());
+    }
+}
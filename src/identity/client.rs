@@ -7,20 +7,25 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+use super::node::PublicId as NodePublicId;
 use crate::keys::{BlsKeypair, SignatureShare};
-use crate::{utils, Error, Keypair, PublicKey, Signature};
-use ed25519_dalek::Keypair as Ed25519Keypair;
+use crate::{utils, Error, Keypair, Proof, PublicKey, Signature};
+use ed25519_dalek::{Keypair as Ed25519Keypair, KEYPAIR_LENGTH, SECRET_KEY_LENGTH};
 use multibase::Decodable;
 use rand::{CryptoRng, Rng};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use signature::Signer;
 use std::fmt::{self, Debug, Display, Formatter};
 use threshold_crypto::{
-    serde_impl::SerdeSecret,
-    SecretKey as BlsSecretKey, //SecretKeyShare as BlsSecretKeyShare,
+    serde_impl::SerdeSecret, PublicKeySet, SecretKey as BlsSecretKey,
+    SecretKeyShare as BlsSecretKeyShare,
 };
+use unwrap::unwrap;
 use xor_name::XorName;
 
+/// Domain separator of the session keys derivation.
+const SESSION_KEY_DOMAIN: &[u8] = b"safe-nd client session key";
+
 /// A struct holding a keypair variant and the corresponding public ID for a network Client.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FullId {
@@ -51,16 +56,80 @@ impl FullId {
         Self { keypair, public_id }
     }
 
-    // /// Constructs a `FullId` from a BLS secret key share.
-    // pub fn new_bls_share(bls_secret_key_share: BlsSecretKeyShare) -> Self {
-    //     let keypair = Keypair::new_bls_share(bls_secret_key_share);
-    //     let public_key = keypair.public_key();
-    //     let public_id = PublicId {
-    //         name: public_key.into(),
-    //         public_key,
-    //     };
-    //     Self { keypair, public_id }
-    // }
+    /// Constructs a `FullId` with a random Ed25519 keypair whose name is in the interval
+    /// [start, end] (both endpoints inclusive).
+    pub fn new_ed25519_within_range<T: CryptoRng + Rng>(
+        start: &XorName,
+        end: &XorName,
+        rng: &mut T,
+    ) -> Self {
+        loop {
+            let full_id = Self::new_ed25519(rng);
+            if full_id.public_id.name >= *start && full_id.public_id.name <= *end {
+                return full_id;
+            }
+        }
+    }
+
+    /// Constructs a `FullId` with a random BLS keypair whose name is in the interval [start, end]
+    /// (both endpoints inclusive).
+    pub fn new_bls_within_range<T: CryptoRng + Rng>(
+        start: &XorName,
+        end: &XorName,
+        rng: &mut T,
+    ) -> Self {
+        loop {
+            let full_id = Self::new_bls(rng);
+            if full_id.public_id.name >= *start && full_id.public_id.name <= *end {
+                return full_id;
+            }
+        }
+    }
+
+    /// Constructs a `FullId` from an Ed25519 secret key, given either as its 32 bytes or as the 64
+    /// bytes of the secret key followed by the public key.
+    pub fn from_ed25519_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (secret_bytes, public_bytes) = match bytes.len() {
+            SECRET_KEY_LENGTH => (bytes, None),
+            KEYPAIR_LENGTH => (
+                &bytes[..SECRET_KEY_LENGTH],
+                Some(&bytes[SECRET_KEY_LENGTH..]),
+            ),
+            len => {
+                return Err(Error::FailedToParse(format!(
+                    "Expected {} or {} bytes of Ed25519 key, got {}",
+                    SECRET_KEY_LENGTH, KEYPAIR_LENGTH, len
+                )))
+            }
+        };
+        let secret = ed25519_dalek::SecretKey::from_bytes(secret_bytes)
+            .map_err(|err| Error::FailedToParse(err.to_string()))?;
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        if let Some(public_bytes) = public_bytes {
+            if public_bytes != &public.as_bytes()[..] {
+                return Err(Error::FailedToParse(
+                    "Ed25519 public key doesn't match the secret key".to_string(),
+                ));
+            }
+        }
+        Ok(Self::from(Ed25519Keypair { secret, public }))
+    }
+
+    /// Constructs a `FullId` from the BLS secret key share at `index` of the key set
+    /// `public_key_set`, e.g. for a client participating in a section.
+    pub fn new_bls_share(
+        bls_secret_key_share: BlsSecretKeyShare,
+        public_key_set: PublicKeySet,
+        index: usize,
+    ) -> Self {
+        let keypair = Keypair::new_bls_share(index, bls_secret_key_share, public_key_set);
+        let public_key = keypair.public_key();
+        let public_id = PublicId {
+            name: public_key.into(),
+            public_key,
+        };
+        Self { keypair, public_id }
+    }
 
     /// Creates a detached signature of `data`.
     pub fn sign<T: AsRef<[u8]>>(&self, data: T) -> Signature {
@@ -74,10 +143,36 @@ impl FullId {
         }
     }
 
+    /// Signs `data`, returning the signature along with the public key to verify it.
+    pub(crate) fn proof(&self, data: &[u8]) -> Proof {
+        self.keypair.proof(data)
+    }
+
     /// Returns the public ID.
     pub fn public_id(&self) -> &PublicId {
         &self.public_id
     }
+
+    /// Deterministically derives an Ed25519 keypair for the session `session_id`.
+    ///
+    /// The derived secret key is a hash of the master secret key and the session id, so the
+    /// session keypair doesn't reveal the master one.
+    pub fn derive_session_key(&self, session_id: &[u8]) -> Keypair {
+        let master_secret = match &self.keypair {
+            Keypair::Ed25519(keys) => keys.secret.as_bytes().to_vec(),
+            Keypair::Bls(keys) => utils::serialise(&keys.secret),
+            Keypair::BlsShare(keys) => utils::serialise(&keys.secret),
+        };
+        let seed = tiny_keccak::sha3_256(&utils::serialise(&(
+            SESSION_KEY_DOMAIN,
+            master_secret,
+            session_id,
+        )));
+        // A secret key can be built from any 32 bytes.
+        let secret = unwrap!(ed25519_dalek::SecretKey::from_bytes(&seed));
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        Keypair::Ed25519(Ed25519Keypair { secret, public })
+    }
 }
 
 impl From<BlsSecretKey> for FullId {
@@ -108,13 +203,6 @@ impl From<Ed25519Keypair> for FullId {
     }
 }
 
-// // This is required so we can have `impl Into<FullId>` as a function parameter
-// impl From<BlsSecretKeyShare> for FullId {
-//     fn from(bls_secret_key_share: BlsSecretKeyShare) -> Self {
-//         Self::new_bls_share(bls_secret_key_share)
-//     }
-// }
-
 /// A struct representing the public identity of a network Client.
 ///
 /// It includes the public signing key, and this provides the Client's network address, i.e.
@@ -147,6 +235,18 @@ impl PublicId {
     }
 }
 
+impl From<&NodePublicId> for PublicId {
+    /// Returns the client view of a Node, e.g. for its own transfers, keyed by its Ed25519 public
+    /// key.
+    fn from(node_public_id: &NodePublicId) -> Self {
+        let public_key = PublicKey::Ed25519(*node_public_id.ed25519_public_key());
+        Self {
+            name: public_key.into(),
+            public_key,
+        }
+    }
+}
+
 impl Serialize for PublicId {
     fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
         (&self.public_key).serialize(serialiser)
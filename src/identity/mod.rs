@@ -17,6 +17,36 @@ use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::sync::Arc;
 
+/// Common behaviour of the full identities held by network entities.
+pub trait Identity {
+    /// Creates a detached signature of `data`.
+    fn sign(&self, data: &[u8]) -> Signature;
+
+    /// Returns the entity's network address.
+    fn name(&self) -> &XorName;
+}
+
+impl Identity for ClientFullId {
+    fn sign(&self, data: &[u8]) -> Signature {
+        ClientFullId::sign(self, data)
+    }
+
+    fn name(&self) -> &XorName {
+        self.public_id().name()
+    }
+}
+
+/// Signs using the Ed25519 keypair, as that is the key the node's name is derived from.
+impl Identity for node::FullId {
+    fn sign(&self, data: &[u8]) -> Signature {
+        self.sign_using_ed25519(data)
+    }
+
+    fn name(&self) -> &XorName {
+        self.public_id().name()
+    }
+}
+
 /// An enum representing the Full Id variants for a Client or App.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SafeKey {
@@ -161,6 +191,157 @@ mod tests {
     use super::*;
     use crate::{ClientFullId, Error};
     use unwrap::unwrap;
+    use xor_name::XOR_NAME_LEN;
+
+    #[test]
+    fn client_session_keys() {
+        let mut rng = rand::thread_rng();
+        for id in vec![
+            ClientFullId::new_ed25519(&mut rng),
+            ClientFullId::new_bls(&mut rng),
+        ] {
+            let session_key = id.derive_session_key(b"session 1");
+            assert_eq!(session_key, id.derive_session_key(b"session 1"));
+            assert_ne!(session_key.public_key(), *id.public_id().public_key());
+            assert_ne!(
+                session_key.public_key(),
+                id.derive_session_key(b"session 2").public_key()
+            );
+
+            let other_id = ClientFullId::new_ed25519(&mut rng);
+            assert_ne!(
+                session_key.public_key(),
+                other_id.derive_session_key(b"session 1").public_key()
+            );
+
+            let signature = session_key.sign(b"data");
+            assert!(session_key.public_key().verify(&signature, b"data").is_ok());
+        }
+    }
+
+    #[test]
+    fn client_bls_share_full_id() {
+        let mut rng = rand::thread_rng();
+        let secret_key_set = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let public_key_set = secret_key_set.public_keys();
+        let id = ClientFullId::new_bls_share(
+            secret_key_set.secret_key_share(2),
+            public_key_set.clone(),
+            2,
+        );
+
+        let public_key_share = public_key_set.public_key_share(2);
+        assert_eq!(
+            *id.public_id().public_key(),
+            PublicKey::BlsShare(public_key_share)
+        );
+        assert_eq!(
+            *id.public_id().name(),
+            XorName::from(PublicKey::BlsShare(public_key_share))
+        );
+
+        let signature = id.sign(b"data");
+        match &signature {
+            Signature::BlsShare(share) => assert_eq!(share.index, 2),
+            _ => panic!("Expected a BLS signature share"),
+        }
+        assert!(id
+            .public_id()
+            .public_key()
+            .verify(&signature, b"data")
+            .is_ok());
+        assert_eq!(
+            id.public_id()
+                .public_key()
+                .verify(&signature, b"other data"),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn client_full_id_within_range() {
+        let mut rng = rand::thread_rng();
+        let mut start = XorName([0; XOR_NAME_LEN]);
+        start.0[0] = 0x40;
+        let mut end = XorName([0xff; XOR_NAME_LEN]);
+        end.0[0] = 0x7f;
+
+        for id in vec![
+            ClientFullId::new_ed25519_within_range(&start, &end, &mut rng),
+            ClientFullId::new_bls_within_range(&start, &end, &mut rng),
+        ] {
+            let name = *id.public_id().name();
+            assert!(name >= start && name <= end);
+        }
+    }
+
+    #[test]
+    fn node_keypairs_rotate_bls() {
+        let mut rng = rand::thread_rng();
+        let mut keypairs = node::NodeKeypairs::new(&mut rng);
+        let ed25519_public_key = *keypairs.public_id().ed25519_public_key();
+
+        let first_set = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let previous =
+            keypairs.rotate_bls(0, first_set.secret_key_share(0), first_set.public_keys());
+        assert_eq!(previous, None);
+
+        let second_set = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let previous =
+            keypairs.rotate_bls(1, second_set.secret_key_share(1), second_set.public_keys());
+        assert_eq!(previous, Some(first_set.public_keys().public_key_share(0)));
+        assert_eq!(
+            keypairs.public_key(),
+            PublicKey::BlsShare(second_set.public_keys().public_key_share(1))
+        );
+        assert_eq!(
+            *keypairs.public_id().ed25519_public_key(),
+            ed25519_public_key
+        );
+    }
+
+    #[test]
+    fn client_public_id_from_node() {
+        let mut rng = rand::thread_rng();
+        let node_id = node::FullId::new(&mut rng);
+        let client_public_id = client::PublicId::from(node_id.public_id());
+        assert_eq!(client_public_id.name(), node_id.public_id().name());
+        assert_eq!(
+            *client_public_id.public_key(),
+            PublicKey::Ed25519(*node_id.public_id().ed25519_public_key())
+        );
+
+        let signature = node_id.sign_using_ed25519(b"data");
+        assert!(client_public_id
+            .public_key()
+            .verify(&signature, b"data")
+            .is_ok());
+    }
+
+    #[test]
+    fn client_full_id_from_ed25519_bytes() {
+        let mut rng = rand::thread_rng();
+        let keypair = ed25519_dalek::Keypair::generate(&mut rng);
+        let id = ClientFullId::from(unwrap!(ed25519_dalek::Keypair::from_bytes(
+            &keypair.to_bytes()
+        )));
+
+        let from_secret = unwrap!(ClientFullId::from_ed25519_bytes(keypair.secret.as_bytes()));
+        assert_eq!(from_secret, id);
+        let from_keypair = unwrap!(ClientFullId::from_ed25519_bytes(&keypair.to_bytes()));
+        assert_eq!(from_keypair, id);
+
+        let mut mismatched = keypair.to_bytes();
+        mismatched[63] ^= 1;
+        assert!(match ClientFullId::from_ed25519_bytes(&mismatched) {
+            Err(Error::FailedToParse(_)) => true,
+            _ => false,
+        });
+        assert!(match ClientFullId::from_ed25519_bytes(&[0; 33]) {
+            Err(Error::FailedToParse(_)) => true,
+            _ => false,
+        });
+    }
 
     #[test]
     fn zbase32_encode_decode_client_public_id() {
@@ -201,6 +382,32 @@ mod tests {
         assert!(node::PublicId::decode_from_zbase32("7djsk38").is_err());
     }
 
+    #[test]
+    fn node_section_public_key() {
+        let mut rng = rand::thread_rng();
+        let bls_secret_key = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let section_key = PublicKey::Bls(bls_secret_key.public_keys().public_key());
+
+        let mut id = node::FullId::new(&mut rng);
+        assert_eq!(id.section_public_key(), None);
+        id.set_bls_keys(
+            bls_secret_key.secret_key_share(0),
+            bls_secret_key.public_keys(),
+        );
+        assert_eq!(id.section_public_key(), Some(section_key));
+        id.clear_bls_keys();
+        assert_eq!(id.section_public_key(), None);
+
+        let mut keypairs = node::NodeKeypairs::new(&mut rng);
+        assert_eq!(keypairs.section_public_key(), None);
+        keypairs.set_bls_keys(
+            1,
+            bls_secret_key.secret_key_share(1),
+            bls_secret_key.public_keys(),
+        );
+        assert_eq!(keypairs.section_public_key(), Some(section_key));
+    }
+
     #[test]
     fn zbase32_encode_decode_app_public_id() {
         let mut rng = rand::thread_rng();
@@ -215,6 +422,25 @@ mod tests {
         assert!(app::PublicId::decode_from_zbase32("7od8fh2").is_err());
     }
 
+    #[test]
+    fn identity_trait() -> Result<()> {
+        fn sign_and_verify(id: &impl Identity, public_key: PublicKey) -> Result<()> {
+            let data = b"some data";
+            assert_eq!(*id.name(), XorName::from(public_key));
+            public_key.verify(&id.sign(data), data)
+        }
+
+        let mut rng = rand::thread_rng();
+        let client_id = client::FullId::new_bls(&mut rng);
+        sign_and_verify(&client_id, *client_id.public_id().public_key())?;
+
+        let node_id = node::FullId::new(&mut rng);
+        sign_and_verify(
+            &node_id,
+            PublicKey::Ed25519(*node_id.public_id().ed25519_public_key()),
+        )
+    }
+
     #[test]
     fn zbase32_encode_decode_enum_public_id() {
         let mut rng = rand::thread_rng();
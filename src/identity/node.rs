@@ -8,7 +8,7 @@
 // Software.
 
 use crate::keys::{BlsKeypairShare, SignatureShare};
-use crate::{utils, Error, PublicKey, Signature};
+use crate::{utils, BlsProofShare, Ed25519Proof, Error, Proof, PublicKey, Signature};
 use ed25519_dalek::{Keypair as Ed25519Keypair, PublicKey as Ed25519PublicKey};
 use hex_fmt::HexFmt;
 use multibase::Decodable;
@@ -93,6 +93,13 @@ impl FullId {
         })
     }
 
+    /// Returns the aggregate BLS public key of the section, if `self` holds a BLS keypair share.
+    pub fn section_public_key(&self) -> Option<PublicKey> {
+        self.bls
+            .as_ref()
+            .map(|keys| PublicKey::Bls(keys.public_key_set.public_key()))
+    }
+
     /// Sets the `FullId`'s BLS keypair share using the provided BLS secret key share.
     pub fn set_bls_keys(&mut self, secret_share: BlsSecretKeyShare, public_set: PublicKeySet) {
         let public = secret_share.public_key_share();
@@ -266,6 +273,12 @@ impl NodeKeypairs {
         self.bls.as_ref().map(|s| &s.public_key_set)
     }
 
+    /// Returns the aggregate BLS public key of the section, if `self` holds a BLS keypair share.
+    pub fn section_public_key(&self) -> Option<PublicKey> {
+        self.public_key_set()
+            .map(|public_key_set| PublicKey::Bls(public_key_set.public_key()))
+    }
+
     /// Signs with the BLS if any, else the Ed25519.
     pub fn sign(&self, data: &[u8]) -> Signature {
         if let Some(sig) = self.sign_using_bls(data) {
@@ -275,6 +288,23 @@ impl NodeKeypairs {
         }
     }
 
+    /// Signs with the BLS if any, else the Ed25519, returning the signature along with the public
+    /// key(s) to verify it.
+    pub(crate) fn proof(&self, data: &[u8]) -> Proof {
+        match &self.bls {
+            Some(keys) => Proof::BlsShare(BlsProofShare::new(
+                keys.public_key_set.clone(),
+                keys.index,
+                &keys.secret,
+                data,
+            )),
+            None => Proof::Ed25519(Ed25519Proof {
+                public_key: self.ed25519.public,
+                signature: self.ed25519.sign(data),
+            }),
+        }
+    }
+
     /// Creates a detached Ed25519 signature of `data`.
     pub fn sign_using_ed25519<T: AsRef<[u8]>>(&self, data: T) -> Signature {
         Signature::Ed25519(self.ed25519.sign(data.as_ref()))
@@ -308,6 +338,19 @@ impl NodeKeypairs {
         });
     }
 
+    /// Replaces the `NodeKeypairs`'s BLS keypair share, e.g. when the section re-keys, returning
+    /// the public key share it held before, if any. The Ed25519 keypair is kept.
+    pub fn rotate_bls(
+        &mut self,
+        index: usize,
+        secret_share: BlsSecretKeyShare,
+        public_set: PublicKeySet,
+    ) -> Option<BlsPublicKeyShare> {
+        let previous = self.public_id.bls;
+        self.set_bls_keys(index, secret_share, public_set);
+        previous
+    }
+
     /// Clears the `NodeKeypairs`'s BLS keypair share, i.e. sets it to `None`.
     pub fn clear_bls_keys(&mut self) {
         self.public_id.bls = None;
@@ -13,11 +13,13 @@
 //! `new` functions. A `PublicKey` can't be generated by itself; it must always be derived from a
 //! secret key.
 
+mod multisig;
 mod proof;
 
 use crate::{utils, Error, Result};
 use hex_fmt::HexFmt;
 use multibase::Decodable;
+pub use multisig::MultisigOwner;
 pub use proof::{BlsProof, BlsProofShare, Ed25519Proof, Proof, Proven};
 use rand::{CryptoRng, Rng};
 use serde::{Deserialize, Serialize};
@@ -27,10 +29,32 @@ use std::{
     fmt::{self, Debug, Display, Formatter},
     hash::{Hash, Hasher},
 };
+use subtle::ConstantTimeEq;
 use threshold_crypto::{self, serde_impl::SerdeSecret};
 use unwrap::unwrap;
 use xor_name::{XorName, XOR_NAME_LEN};
 
+/// Key type of the JSON Web Keys for signing curves, such as Ed25519 (RFC 8037).
+const JWK_KEY_TYPE: &str = "OKP";
+/// JSON Web Key curve of Ed25519 keys.
+const JWK_ED25519_CURVE: &str = "Ed25519";
+/// JSON Web Key curve of BLS keys. Not registered, so specific to this crate.
+const JWK_BLS_CURVE: &str = "BLS12381G1";
+
+/// Variant tags prefixing the bytes of the keys encoded by `PublicKey::to_hex`.
+const HEX_ED25519_TAG: u8 = 0;
+const HEX_BLS_TAG: u8 = 1;
+const HEX_BLS_SHARE_TAG: u8 = 2;
+
+/// Signature algorithm of a key.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum KeyAlgorithm {
+    /// Ed25519.
+    Ed25519,
+    /// BLS, including BLS key shares.
+    Bls,
+}
+
 /// Wrapper for different public key types.
 #[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PublicKey {
@@ -43,6 +67,14 @@ pub enum PublicKey {
 }
 
 impl PublicKey {
+    /// Returns the signature algorithm of the key.
+    pub fn kind(&self) -> KeyAlgorithm {
+        match self {
+            Self::Ed25519(_) => KeyAlgorithm::Ed25519,
+            Self::Bls(_) | Self::BlsShare(_) => KeyAlgorithm::Bls,
+        }
+    }
+
     /// Returns the ed25519 key, if applicable.
     pub fn ed25519(&self) -> Option<ed25519_dalek::PublicKey> {
         if let Self::Ed25519(key) = self {
@@ -88,6 +120,145 @@ impl PublicKey {
         }
     }
 
+    /// Returns the bytes of the ed25519 key, if applicable.
+    pub fn as_ed25519_bytes(&self) -> Option<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]> {
+        self.ed25519().map(|key| key.to_bytes())
+    }
+
+    /// Returns `true` if `shares` is not empty and each of the BLS signature shares is a valid
+    /// signature of `data` by its public key share, e.g. when collecting a quorum of shares over
+    /// the same payload.
+    ///
+    /// The shares are verified one by one, so this costs as much as verifying each of them.
+    pub fn verify_shares(
+        shares: &[(threshold_crypto::PublicKeyShare, SignatureShare)],
+        data: &[u8],
+    ) -> bool {
+        !shares.is_empty()
+            && shares
+                .iter()
+                .all(|(public_key, signature)| public_key.verify(&signature.share, data))
+    }
+
+    /// Returns the key encoded in hex, prefixed with a byte telling its variant.
+    pub fn to_hex(&self) -> String {
+        let mut bytes = Vec::with_capacity(threshold_crypto::PK_SIZE + 1);
+        match self {
+            Self::Ed25519(pub_key) => {
+                bytes.push(HEX_ED25519_TAG);
+                bytes.extend_from_slice(pub_key.as_bytes());
+            }
+            Self::Bls(pub_key) => {
+                bytes.push(HEX_BLS_TAG);
+                bytes.extend_from_slice(&pub_key.to_bytes());
+            }
+            Self::BlsShare(pub_key) => {
+                bytes.push(HEX_BLS_SHARE_TAG);
+                bytes.extend_from_slice(&pub_key.to_bytes());
+            }
+        }
+        hex::encode(bytes)
+    }
+
+    /// Creates from a key encoded with `to_hex`.
+    ///
+    /// Returns `Error::FailedToParse` if the string is not hex, or has an unknown variant tag or
+    /// the wrong length for its variant.
+    pub fn from_hex(encoded: &str) -> Result<Self> {
+        let bytes =
+            hex::decode(encoded).map_err(|error| Error::FailedToParse(error.to_string()))?;
+        let (tag, key) = bytes
+            .split_first()
+            .ok_or_else(|| Error::FailedToParse("Empty public key".to_string()))?;
+        let bls_bytes = || {
+            if key.len() != threshold_crypto::PK_SIZE {
+                return Err(Error::FailedToParse(format!(
+                    "Invalid BLS public key length {}",
+                    key.len()
+                )));
+            }
+            let mut bls_bytes = [0; threshold_crypto::PK_SIZE];
+            bls_bytes.copy_from_slice(key);
+            Ok(bls_bytes)
+        };
+        match *tag {
+            HEX_ED25519_TAG => ed25519_dalek::PublicKey::from_bytes(key)
+                .map(Self::Ed25519)
+                .map_err(|error| Error::FailedToParse(error.to_string())),
+            HEX_BLS_TAG => threshold_crypto::PublicKey::from_bytes(bls_bytes()?)
+                .map(Self::Bls)
+                .map_err(|error| Error::FailedToParse(error.to_string())),
+            HEX_BLS_SHARE_TAG => threshold_crypto::PublicKeyShare::from_bytes(bls_bytes()?)
+                .map(Self::BlsShare)
+                .map_err(|error| Error::FailedToParse(error.to_string())),
+            tag => Err(Error::FailedToParse(format!(
+                "Unknown public key variant tag {}",
+                tag
+            ))),
+        }
+    }
+
+    /// Returns the key as a JSON Web Key (RFC 8037), i.e. an `OKP` key on the `Ed25519` curve, or
+    /// on the non-standard `BLS12381G1` curve for BLS keys.
+    ///
+    /// Returns `Error::InvalidOperation` for BLS key shares, which have no JWK form.
+    pub fn to_jwk(&self) -> Result<serde_json::Value> {
+        let (curve, bytes) = match self {
+            Self::Ed25519(pub_key) => (JWK_ED25519_CURVE, pub_key.to_bytes().to_vec()),
+            Self::Bls(pub_key) => (JWK_BLS_CURVE, pub_key.to_bytes().to_vec()),
+            Self::BlsShare(_) => return Err(Error::InvalidOperation),
+        };
+        Ok(serde_json::json!({
+            "kty": JWK_KEY_TYPE,
+            "crv": curve,
+            "x": base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD),
+        }))
+    }
+
+    /// Creates from a JSON Web Key, as produced by `to_jwk`.
+    pub fn from_jwk(jwk: &serde_json::Value) -> Result<Self> {
+        let key_type = jwk_field(jwk, "kty")?;
+        if key_type != JWK_KEY_TYPE {
+            return Err(Error::FailedToParse(format!(
+                "Unsupported JWK key type {}",
+                key_type
+            )));
+        }
+        let bytes = base64::decode_config(jwk_field(jwk, "x")?, base64::URL_SAFE_NO_PAD)
+            .map_err(|error| Error::FailedToParse(error.to_string()))?;
+        match jwk_field(jwk, "crv")? {
+            JWK_ED25519_CURVE => ed25519_dalek::PublicKey::from_bytes(&bytes)
+                .map(Self::Ed25519)
+                .map_err(|error| Error::FailedToParse(error.to_string())),
+            JWK_BLS_CURVE => {
+                if bytes.len() != threshold_crypto::PK_SIZE {
+                    return Err(Error::FailedToParse(format!(
+                        "Invalid BLS public key length {}",
+                        bytes.len()
+                    )));
+                }
+                let mut pub_key = [0; threshold_crypto::PK_SIZE];
+                pub_key.copy_from_slice(&bytes);
+                threshold_crypto::PublicKey::from_bytes(pub_key)
+                    .map(Self::Bls)
+                    .map_err(|error| Error::FailedToParse(error.to_string()))
+            }
+            curve => Err(Error::FailedToParse(format!(
+                "Unsupported JWK curve {}",
+                curve
+            ))),
+        }
+    }
+
+    /// Returns `true` if this is an all-zero placeholder key, which no one can sign for.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Ed25519(pub_key) => pub_key.as_bytes().iter().all(|byte| *byte == 0),
+            Self::Bls(pub_key) => pub_key.to_bytes().iter().all(|byte| *byte == 0),
+            Self::BlsShare(pub_key) => pub_key.to_bytes().iter().all(|byte| *byte == 0),
+        }
+    }
+
     /// Returns the `PublicKey` serialised and encoded in z-base-32.
     pub fn encode_to_zbase32(&self) -> String {
         utils::encode(&self)
@@ -99,6 +270,13 @@ impl PublicKey {
     }
 }
 
+/// Returns the string value of the `name` field of `jwk`.
+fn jwk_field<'a>(jwk: &'a serde_json::Value, name: &str) -> Result<&'a str> {
+    jwk.get(name)
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| Error::FailedToParse(format!("Missing JWK field {}", name)))
+}
+
 #[allow(clippy::derive_hash_xor_eq)]
 impl Hash for PublicKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -213,6 +391,47 @@ impl Signature {
             _ => None,
         }
     }
+
+    /// Verifies that this is a signature of `data` by `key`.
+    ///
+    /// Returns `Error::InvalidSignature` if it is not, including if the signature is not of the
+    /// same kind as the key, e.g. a BLS signature checked against an Ed25519 key.
+    pub fn verify(&self, key: &PublicKey, data: &[u8]) -> Result<()> {
+        key.verify(self, data).map_err(|_| Error::InvalidSignature)
+    }
+
+    /// Combines the BLS signature shares of `data` into a signature by the key of `public_key_set`.
+    ///
+    /// Returns `Error::InvalidSignature` if there are not enough shares, or if the combined
+    /// signature is not valid, i.e. if any of the shares was not.
+    pub fn combine_shares(
+        public_key_set: &threshold_crypto::PublicKeySet,
+        shares: &[SignatureShare],
+        data: &[u8],
+    ) -> Result<Self> {
+        let signature = public_key_set
+            .combine_signatures(shares.iter().map(|share| (share.index, &share.share)))
+            .map_err(|_| Error::InvalidSignature)?;
+        if !public_key_set.public_key().verify(&signature, data) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(Self::Bls(signature))
+    }
+
+    /// Returns the bytes of the ed25519 signature, if applicable.
+    pub fn as_ed25519_bytes(&self) -> Option<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+        match self {
+            Self::Ed25519(sig) => Some(sig.to_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Compares with `other` in constant time.
+    ///
+    /// Prefer this over `==` wherever a signature is matched against an expected value.
+    pub fn ct_eq(&self, other: &Signature) -> bool {
+        utils::ct_eq_serialised(self, other)
+    }
 }
 
 impl From<threshold_crypto::Signature> for Signature {
@@ -324,6 +543,19 @@ impl PartialEq for Keypair {
 impl Eq for Keypair {}
 
 impl Keypair {
+    /// Constructs a random public keypair for the signature algorithm `algorithm`.
+    pub fn generate<T: CryptoRng + Rng>(algorithm: KeyAlgorithm, rng: &mut T) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Self::new_ed25519(rng),
+            KeyAlgorithm::Bls => Self::new_bls(rng),
+        }
+    }
+
+    /// Returns the signature algorithm of the keypair.
+    pub fn kind(&self) -> KeyAlgorithm {
+        self.public_key().kind()
+    }
+
     /// Constructs a random Ed25519 public keypair.
     pub fn new_ed25519<T: CryptoRng + Rng>(rng: &mut T) -> Self {
         let keypair = ed25519_dalek::Keypair::generate(rng);
@@ -366,6 +598,26 @@ impl Keypair {
         }
     }
 
+    /// Returns `true` if both keypairs are of the same variant and hold the same secret key.
+    ///
+    /// The secret keys are compared in constant time.
+    pub fn same_secret(&self, other: &Keypair) -> bool {
+        match (self, other) {
+            (Self::Ed25519(keypair), Self::Ed25519(other_keypair)) => keypair
+                .secret
+                .as_bytes()
+                .ct_eq(other_keypair.secret.as_bytes())
+                .into(),
+            (Self::Bls(keypair), Self::Bls(other_keypair)) => {
+                utils::ct_eq_serialised(&keypair.secret, &other_keypair.secret)
+            }
+            (Self::BlsShare(keypair), Self::BlsShare(other_keypair)) => {
+                utils::ct_eq_serialised(&keypair.secret, &other_keypair.secret)
+            }
+            _ => false,
+        }
+    }
+
     /// Signs with the underlying keypair.
     pub fn sign(&self, data: &[u8]) -> Signature {
         match self {
@@ -378,6 +630,26 @@ impl Keypair {
             }
         }
     }
+
+    /// Signs `data`, returning the signature along with the public key(s) to verify it.
+    pub(crate) fn proof(&self, data: &[u8]) -> Proof {
+        match self {
+            Self::Ed25519(keypair) => Proof::Ed25519(Ed25519Proof {
+                public_key: keypair.public,
+                signature: keypair.sign(data),
+            }),
+            Self::Bls(keypair) => Proof::Bls(BlsProof {
+                public_key: keypair.public,
+                signature: keypair.secret.sign(data),
+            }),
+            Self::BlsShare(keypair) => Proof::BlsShare(BlsProofShare::new(
+                keypair.public_key_set.clone(),
+                keypair.index,
+                &keypair.secret,
+                data,
+            )),
+        }
+    }
 }
 
 /// BLS keypair.
@@ -407,6 +679,7 @@ mod tests {
     use super::*;
     use crate::utils;
     use bincode::deserialize as deserialise;
+    use std::convert::TryFrom;
     use threshold_crypto::{self};
 
     fn gen_keypairs() -> Vec<Keypair> {
@@ -427,6 +700,25 @@ mod tests {
         gen_keypairs().iter().map(PublicKey::from).collect()
     }
 
+    #[test]
+    fn keypair_generate() {
+        let mut rng = rand::thread_rng();
+        for algorithm in vec![KeyAlgorithm::Ed25519, KeyAlgorithm::Bls] {
+            let keypair = Keypair::generate(algorithm, &mut rng);
+            assert_eq!(keypair.kind(), algorithm);
+            assert_eq!(keypair.public_key().kind(), algorithm);
+
+            let signature = keypair.sign(b"data");
+            assert!(keypair.public_key().verify(&signature, b"data").is_ok());
+        }
+        assert!(match Keypair::generate(KeyAlgorithm::Bls, &mut rng) {
+            Keypair::Bls(_) => true,
+            _ => false,
+        });
+        // A BLS key share uses the BLS algorithm too.
+        assert_eq!(gen_keypairs()[2].kind(), KeyAlgorithm::Bls);
+    }
+
     #[test]
     fn zbase32_encode_decode_public_key() {
         use unwrap::unwrap;
@@ -466,4 +758,225 @@ mod tests {
             assert_eq!(decoded, keypair);
         }
     }
+
+    #[test]
+    fn signature_ct_eq() {
+        let data = b"some data";
+        let signatures: Vec<_> = gen_keypairs()
+            .iter()
+            .map(|keypair| keypair.sign(data))
+            .collect();
+
+        for lhs in &signatures {
+            for rhs in &signatures {
+                assert_eq!(lhs.ct_eq(rhs), lhs == rhs);
+            }
+        }
+        assert!(signatures[0].ct_eq(&signatures[0].clone()));
+        assert!(!signatures[0].ct_eq(&signatures[1]));
+    }
+
+    #[test]
+    fn keypair_same_secret() {
+        let keypairs = gen_keypairs();
+        let others = gen_keypairs();
+        for (keypair, other) in keypairs.iter().zip(&others) {
+            assert!(keypair.same_secret(&keypair.clone()));
+            assert!(!keypair.same_secret(other));
+        }
+        assert!(!keypairs[0].same_secret(&keypairs[1]));
+    }
+
+    #[test]
+    fn signature_verify() -> Result<()> {
+        let data = b"some data";
+        let keypairs = gen_keypairs();
+        for keypair in &keypairs {
+            let signature = keypair.sign(data);
+            signature.verify(&keypair.public_key(), data)?;
+            assert_eq!(
+                signature.verify(&keypair.public_key(), b"other data"),
+                Err(Error::InvalidSignature)
+            );
+            for other_keypair in &keypairs {
+                if other_keypair.public_key() != keypair.public_key() {
+                    assert_eq!(
+                        signature.verify(&other_keypair.public_key(), data),
+                        Err(Error::InvalidSignature)
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bls_shares() -> Result<()> {
+        let data = b"some data";
+        let mut rng = rand::thread_rng();
+        let secret_key_set = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let public_key_set = secret_key_set.public_keys();
+        let shares: Vec<_> = (0..3)
+            .map(|index| {
+                let share = SignatureShare {
+                    index,
+                    share: secret_key_set.secret_key_share(index).sign(data),
+                };
+                (public_key_set.public_key_share(index), share)
+            })
+            .collect();
+        assert!(PublicKey::verify_shares(&shares, data));
+        assert!(!PublicKey::verify_shares(&shares, b"other data"));
+        assert!(!PublicKey::verify_shares(&[], data));
+
+        let signature_shares: Vec<_> = shares.iter().map(|(_, share)| share.clone()).collect();
+        let signature = Signature::combine_shares(&public_key_set, &signature_shares, data)?;
+        assert!(PublicKey::Bls(public_key_set.public_key())
+            .verify(&signature, data)
+            .is_ok());
+
+        // Not enough shares for the threshold of 1.
+        assert_eq!(
+            Signature::combine_shares(&public_key_set, &signature_shares[..1], data),
+            Err(Error::InvalidSignature)
+        );
+        // A share signing other data.
+        let mut forged_shares = signature_shares;
+        forged_shares[1].share = secret_key_set.secret_key_share(1).sign(b"other data");
+        assert_eq!(
+            Signature::combine_shares(&public_key_set, &forged_shares[..2], data),
+            Err(Error::InvalidSignature)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn public_key_hex() -> Result<()> {
+        for keypair in gen_keypairs() {
+            let public_key = keypair.public_key();
+            let encoded = public_key.to_hex();
+            assert_eq!(PublicKey::from_hex(&encoded)?, public_key);
+
+            // Truncated key.
+            assert!(PublicKey::from_hex(&encoded[..encoded.len() - 2]).is_err());
+            // Unknown variant tag.
+            assert!(PublicKey::from_hex(&format!("ff{}", &encoded[2..])).is_err());
+        }
+        assert!(PublicKey::from_hex("").is_err());
+        assert!(PublicKey::from_hex("not hex").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn public_key_jwk() -> Result<()> {
+        for keypair in gen_keypairs() {
+            let public_key = keypair.public_key();
+            match public_key {
+                PublicKey::Ed25519(_) | PublicKey::Bls(_) => {
+                    let jwk = public_key.to_jwk()?;
+                    assert_eq!(jwk["kty"], "OKP");
+                    assert_eq!(PublicKey::from_jwk(&jwk)?, public_key);
+                }
+                PublicKey::BlsShare(_) => {
+                    assert_eq!(public_key.to_jwk(), Err(Error::InvalidOperation))
+                }
+            }
+        }
+
+        let ed25519_key =
+            PublicKey::Ed25519(ed25519_dalek::Keypair::generate(&mut rand::thread_rng()).public);
+        let mut jwk = ed25519_key.to_jwk()?;
+        assert_eq!(jwk["crv"], "Ed25519");
+        jwk["crv"] = "X25519".into();
+        assert!(PublicKey::from_jwk(&jwk).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ed25519_bytes() {
+        let data = b"some data";
+        for keypair in gen_keypairs() {
+            let public_key = keypair.public_key();
+            let signature = keypair.sign(data);
+            match keypair {
+                Keypair::Ed25519(ref keypair) => {
+                    assert_eq!(
+                        public_key.as_ed25519_bytes(),
+                        Some(keypair.public.to_bytes())
+                    );
+                    let bytes = unwrap!(signature.as_ed25519_bytes());
+                    let sig = unwrap!(ed25519_dalek::Signature::try_from(&bytes[..]));
+                    assert_eq!(Signature::Ed25519(sig), signature);
+                }
+                _ => {
+                    assert_eq!(public_key.as_ed25519_bytes(), None);
+                    assert_eq!(signature.as_ed25519_bytes(), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn multisig_owner_threshold() -> Result<()> {
+        let data = b"some data";
+        let keypairs: Vec<_> = (0..3)
+            .map(|_| Keypair::new_ed25519(&mut rand::thread_rng()))
+            .collect();
+        let keys = keypairs.iter().map(PublicKey::from).collect();
+        let owner = MultisigOwner::new(keys, 2)?;
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| (keypair.public_key(), keypair.sign(data)))
+            .collect();
+
+        assert_eq!(
+            owner.verify(&signatures[..1], data),
+            Err(Error::AccessDenied)
+        );
+        // The same signer counts once.
+        let repeated = vec![signatures[0].clone(), signatures[0].clone()];
+        assert_eq!(owner.verify(&repeated, data), Err(Error::AccessDenied));
+        owner.verify(&signatures[..2], data)?;
+        owner.verify(&signatures, data)?;
+
+        let forged = vec![
+            signatures[0].clone(),
+            (signatures[1].0, signatures[2].1.clone()),
+        ];
+        assert_eq!(owner.verify(&forged, data), Err(Error::InvalidSignature));
+
+        assert_eq!(
+            MultisigOwner::new(vec![signatures[0].0], 2),
+            Err(Error::InvalidOwners)
+        );
+        let single = MultisigOwner::from(signatures[0].0);
+        single.verify(&signatures[..1], data)
+    }
+
+    #[test]
+    fn multisig_owner_deserialisation() -> Result<()> {
+        #[derive(Serialize)]
+        struct UncheckedMultisigOwner {
+            keys: Vec<PublicKey>,
+            threshold: usize,
+        }
+
+        let keys: Vec<_> = (0..2)
+            .map(|_| Keypair::new_ed25519(&mut rand::thread_rng()).public_key())
+            .collect();
+        let owner = MultisigOwner::new(keys.clone(), 2)?;
+        let decoded: MultisigOwner = unwrap!(deserialise(&utils::serialise(&owner)));
+        assert_eq!(decoded, owner);
+
+        for (keys, threshold) in vec![
+            (vec![], 0),
+            (keys.clone(), 0),
+            (keys.clone(), 3),
+            (vec![keys[0], keys[0]], 1),
+        ] {
+            let unchecked = UncheckedMultisigOwner { keys, threshold };
+            assert!(deserialise::<MultisigOwner>(&utils::serialise(&unchecked)).is_err());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,101 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{PublicKey, Signature};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, convert::TryFrom};
+
+/// An m-of-n owner: any `threshold` of the `keys` together act as the owner.
+///
+/// A single `PublicKey` converts into a 1-of-1 `MultisigOwner`. The owners of the data types are
+/// still single `PublicKey`s, so checking their signatures against a `MultisigOwner` is up to the
+/// caller.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+#[serde(try_from = "UncheckedMultisigOwner")]
+pub struct MultisigOwner {
+    keys: Vec<PublicKey>,
+    threshold: usize,
+}
+
+/// A deserialised `MultisigOwner`, before the checks of `MultisigOwner::new`.
+#[derive(Deserialize)]
+struct UncheckedMultisigOwner {
+    keys: Vec<PublicKey>,
+    threshold: usize,
+}
+
+impl TryFrom<UncheckedMultisigOwner> for MultisigOwner {
+    type Error = Error;
+
+    fn try_from(owner: UncheckedMultisigOwner) -> Result<Self> {
+        Self::new(owner.keys, owner.threshold)
+    }
+}
+
+impl MultisigOwner {
+    /// Constructs a new m-of-n owner.
+    ///
+    /// Returns `Error::InvalidOwners` if `keys` contains duplicates, or if `threshold` is zero or
+    /// greater than the number of keys.
+    pub fn new(keys: Vec<PublicKey>, threshold: usize) -> Result<Self> {
+        let unique: BTreeSet<_> = keys.iter().collect();
+        if unique.len() != keys.len() || threshold == 0 || threshold > keys.len() {
+            return Err(Error::InvalidOwners);
+        }
+        Ok(Self { keys, threshold })
+    }
+
+    /// Returns the keys of the owner.
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    /// Returns the number of signatures required.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Verifies that at least `threshold` distinct owner keys have signed `data`.
+    ///
+    /// Returns the verification error if any of the signatures by an owner key is invalid, and
+    /// `Error::AccessDenied` if too few owner keys have signed or the threshold is zero.
+    /// Signatures by other keys are ignored.
+    pub fn verify<T: AsRef<[u8]>>(
+        &self,
+        signatures: &[(PublicKey, Signature)],
+        data: T,
+    ) -> Result<()> {
+        if self.threshold == 0 {
+            return Err(Error::AccessDenied);
+        }
+        let mut signers = BTreeSet::new();
+        for (key, signature) in signatures {
+            if !self.keys.contains(key) {
+                continue;
+            }
+            key.verify(signature, data.as_ref())?;
+            let _ = signers.insert(*key);
+        }
+        if signers.len() >= self.threshold {
+            Ok(())
+        } else {
+            Err(Error::AccessDenied)
+        }
+    }
+}
+
+impl From<PublicKey> for MultisigOwner {
+    fn from(key: PublicKey) -> Self {
+        Self {
+            keys: vec![key],
+            threshold: 1,
+        }
+    }
+}
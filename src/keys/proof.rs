@@ -12,6 +12,7 @@ use std::{
     fmt::{self, Debug, Formatter},
     hash::{Hash, Hasher},
 };
+use subtle::ConstantTimeEq;
 
 ///
 #[derive(Debug, Hash, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -54,6 +55,13 @@ impl Proof {
             Ed25519(proof) => proof.verify(payload),
         }
     }
+
+    /// Compares with `other` in constant time.
+    ///
+    /// Prefer this over `==` wherever a proof is matched against an expected value.
+    pub fn ct_eq(&self, other: &Proof) -> bool {
+        utils::ct_eq_serialised(self, other)
+    }
 }
 
 ///
@@ -83,11 +91,15 @@ impl Ed25519Proof {
 }
 
 // Need to manually implement this due to a missing impl in `Ed25519::Keypair`.
+// The comparison is constant-time, as proofs are matched against expected values.
 impl PartialEq for Ed25519Proof {
     fn eq(&self, other: &Self) -> bool {
-        // TODO: After const generics land, remove the `to_vec()` calls.
-        self.public_key.to_bytes().to_vec() == other.public_key.to_bytes().to_vec()
-            && self.signature.to_bytes().to_vec() == other.signature.to_bytes().to_vec()
+        let public_key_eq = self
+            .public_key
+            .as_bytes()
+            .ct_eq(other.public_key.as_bytes());
+        let signature_eq = self.signature.to_bytes()[..].ct_eq(&other.signature.to_bytes()[..]);
+        (public_key_eq & signature_eq).into()
     }
 }
 
@@ -35,9 +35,12 @@ mod keys;
 mod map;
 mod messaging;
 mod money;
+mod quota;
 mod rewards;
 mod sequence;
+mod snapshot;
 mod transfer;
+mod type_tag;
 mod utils;
 
 pub use blob::{
@@ -49,11 +52,11 @@ pub use identity::{
     app::{FullId as AppFullId, PublicId as AppPublicId},
     client::{FullId as ClientFullId, PublicId as ClientPublicId},
     node::{FullId as NodeFullId, NodeKeypairs, PublicId as NodePublicId},
-    PublicId, SafeKey,
+    Identity, PublicId, SafeKey,
 };
 pub use keys::{
-    BlsKeypair, BlsKeypairShare, BlsProof, BlsProofShare, Ed25519Proof, Keypair, Proof, Proven,
-    PublicKey, Signature, SignatureShare,
+    BlsKeypair, BlsKeypairShare, BlsProof, BlsProofShare, Ed25519Proof, KeyAlgorithm, Keypair,
+    MultisigOwner, Proof, Proven, PublicKey, Signature, SignatureShare,
 };
 pub use map::{
     Action as MapAction, Address as MapAddress, Data as Map, Entries as MapEntries,
@@ -65,27 +68,39 @@ pub use map::{
 };
 pub use messaging::*;
 pub use money::Money;
+pub use quota::{filter_owned_by, StorageQuota};
 pub use rewards::{RewardCounter, Work};
 
 pub use sequence::{
-    Action as SequenceAction, Address as SequenceAddress, Data as Sequence,
+    Action as SequenceAction, Address as SequenceAddress, BatchWriteOp as SequenceBatchWriteOp,
+    Data as Sequence, DataChange as SequenceDataChange, DataWriteOp as SequenceDataWriteOp,
     Entries as SequenceEntries, Entry as SequenceEntry, Index as SequenceIndex,
-    Indices as SequenceIndices, Kind as SequenceKind, Owner as SequenceOwner,
-    Permissions as SequencePermissions, PrivSeqData,
+    Indices as SequenceIndices, Kind as SequenceKind, MerkleProof as SequenceMerkleProof,
+    OpBuffer as SequenceOpBuffer, Owner as SequenceOwner, OwnerProposal as SequenceOwnerProposal,
+    OwnerProposalOp as SequenceOwnerProposalOp, Permissions as SequencePermissions, PrivSeqData,
     PrivUserPermissions as SequencePrivUserPermissions,
     PrivatePermissions as SequencePrivatePermissions, PubSeqData,
     PubUserPermissions as SequencePubUserPermissions,
-    PublicPermissions as SequencePublicPermissions, User as SequenceUser,
-    UserPermissions as SequenceUserPermissions, WriteOp as SequenceWriteOp,
+    PublicPermissions as SequencePublicPermissions, Transaction as SequenceTransaction,
+    User as SequenceUser, UserPermissions as SequenceUserPermissions, WriteOp as SequenceWriteOp,
 };
 pub use sha3::Sha3_512 as Ed25519Digest;
+pub use snapshot::SignedSnapshot;
 pub use transfer::*;
+pub use type_tag::TypeTag;
 pub use utils::verify_signature;
 
 use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, net::SocketAddr};
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    net::SocketAddr,
+    str::FromStr,
+};
 use xor_name::XorName;
 
+/// Unit, in bytes, in which data is billed for storage.
+pub const STORE_COST_UNIT_IN_BYTES: u64 = 1024;
+
 /// Object storing a data variant.
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -112,6 +127,87 @@ impl Data {
     pub fn is_unpub(&self) -> bool {
         !self.is_pub()
     }
+
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        match *self {
+            Self::Immutable(ref idata) => idata.serialised_size(),
+            Self::Mutable(ref mdata) => utils::serialise(mdata).len() as u64,
+            Self::Sequence(ref sequence) => utils::serialise(sequence).len() as u64,
+        }
+    }
+
+    /// Returns the key the stored bytes of the data are attributed to, i.e. its current owner, or
+    /// `None` for a public Blob or a Sequence without owner.
+    pub fn owner_quota_key(&self) -> Option<PublicKey> {
+        match *self {
+            Self::Immutable(Blob::Private(ref idata)) => Some(*idata.owner()),
+            Self::Immutable(Blob::Public(_)) => None,
+            Self::Mutable(ref mdata) => Some(mdata.owner()),
+            Self::Sequence(ref sequence) => sequence
+                .owner(SequenceIndex::FromEnd(1))
+                .map(|owner| owner.public_key),
+        }
+    }
+
+    /// Returns the cmd deleting the data, e.g. to purge it when its owner's account is closed, or
+    /// `None` for public data, which can't be deleted.
+    pub fn mark_for_deletion(&self) -> Option<DataCmd> {
+        match *self {
+            Self::Immutable(Blob::Private(ref idata)) => {
+                Some(DataCmd::Blob(BlobWrite::DeletePrivate(*idata.address())))
+            }
+            Self::Immutable(Blob::Public(_)) => None,
+            Self::Mutable(ref mdata) => Some(DataCmd::Map(MapWrite::Delete(*mdata.address()))),
+            Self::Sequence(ref sequence) if sequence.is_private() => Some(DataCmd::Sequence(
+                SequenceWrite::Delete(*sequence.address()),
+            )),
+            Self::Sequence(_) => None,
+        }
+    }
+
+    /// Returns the number of bytes the data is billed for when stored, i.e. its serialised size
+    /// rounded up to a whole number of `STORE_COST_UNIT_IN_BYTES`.
+    pub fn store_cost_bytes(&self) -> u64 {
+        let size = self.serialised_size();
+        let units =
+            size / STORE_COST_UNIT_IN_BYTES + u64::from(size % STORE_COST_UNIT_IN_BYTES != 0);
+        units.saturating_mul(STORE_COST_UNIT_IN_BYTES)
+    }
+
+    /// Takes a snapshot of Map or Sequence data, signed by `signer` as the current owner.
+    ///
+    /// Returns `Error::AccessDenied` if `signer` is not the current owner, and
+    /// `Error::InvalidOperation` for a Blob, whose integrity is already given by its address.
+    pub fn sign_snapshot(&self, signer: &Keypair) -> Result<SignedSnapshot> {
+        let owner = match *self {
+            Self::Immutable(_) => return Err(Error::InvalidOperation),
+            Self::Mutable(ref mdata) => mdata.owner(),
+            Self::Sequence(ref sequence) => {
+                sequence
+                    .owner(SequenceIndex::FromEnd(1))
+                    .ok_or(Error::InvalidOwners)?
+                    .public_key
+            }
+        };
+        if signer.public_key() != owner {
+            return Err(Error::AccessDenied);
+        }
+        Ok(SignedSnapshot {
+            data: self.clone(),
+            signature: signer.sign(&utils::serialise(self)),
+        })
+    }
+
+    /// Returns `Error::InvalidOwners` if the data is owned by a zero key, i.e. could never be
+    /// controlled by anyone.
+    pub fn validate_owner(&self) -> Result<()> {
+        match *self {
+            Self::Immutable(ref idata) => idata.validate_owner(),
+            Self::Mutable(ref mdata) => mdata.validate_owner(),
+            Self::Sequence(ref sequence) => sequence.validate_owner(),
+        }
+    }
 }
 
 impl From<Blob> for Data {
@@ -132,6 +228,84 @@ impl From<Sequence> for Data {
     }
 }
 
+/// Scheme prefixing the addresses formatted by `AnyAddress`.
+const URI_SCHEME: &str = "safe://";
+
+/// Address of any kind of data.
+///
+/// Formats as `safe://<kind>/<z-base-32 address>`, with kind `blob`, `map` or `sequence`, which
+/// `FromStr` parses back.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum AnyAddress {
+    /// Address of a Blob.
+    Blob(BlobAddress),
+    /// Address of a Map.
+    Map(MapAddress),
+    /// Address of a Sequence.
+    Sequence(SequenceAddress),
+}
+
+impl From<BlobAddress> for AnyAddress {
+    fn from(address: BlobAddress) -> Self {
+        Self::Blob(address)
+    }
+}
+
+impl From<MapAddress> for AnyAddress {
+    fn from(address: MapAddress) -> Self {
+        Self::Map(address)
+    }
+}
+
+impl From<SequenceAddress> for AnyAddress {
+    fn from(address: SequenceAddress) -> Self {
+        Self::Sequence(address)
+    }
+}
+
+impl Display for AnyAddress {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let (kind, encoded) = match *self {
+            Self::Blob(ref address) => ("blob", address.encode_to_zbase32()),
+            Self::Map(ref address) => ("map", address.encode_to_zbase32()),
+            Self::Sequence(ref address) => ("sequence", address.encode_to_zbase32()),
+        };
+        write!(formatter, "{}{}/{}", URI_SCHEME, kind, encoded)
+    }
+}
+
+impl FromStr for AnyAddress {
+    type Err = Error;
+
+    /// Parses an address formatted by `Display`, dispatching on the kind to the address parsing
+    /// of that data type.
+    ///
+    /// Returns `Error::FailedToParse` if the scheme or kind is unknown, or the address can't be
+    /// decoded.
+    fn from_str(value: &str) -> Result<Self> {
+        if !value.starts_with(URI_SCHEME) {
+            return Err(Error::FailedToParse(format!(
+                "Expected {} scheme in {:?}",
+                URI_SCHEME, value
+            )));
+        }
+        let mut parts = value[URI_SCHEME.len()..].splitn(2, '/');
+        let kind = parts.next().unwrap_or_default();
+        let encoded = parts
+            .next()
+            .ok_or_else(|| Error::FailedToParse(format!("Missing address in {:?}", value)))?;
+        match kind {
+            "blob" => BlobAddress::decode_from_zbase32(encoded).map(Self::Blob),
+            "map" => MapAddress::decode_from_zbase32(encoded).map(Self::Map),
+            "sequence" => SequenceAddress::decode_from_zbase32(encoded).map(Self::Sequence),
+            _ => Err(Error::FailedToParse(format!(
+                "Unknown data kind {:?}",
+                kind
+            ))),
+        }
+    }
+}
+
 /// Permissions for an app stored by the Client Handlers.
 #[derive(
     Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Default, Debug,
@@ -173,3 +347,175 @@ pub enum HandshakeResponse {
     /// Sent by nodes as a response to an invalid `HandshakeRequest::Join` (when a client attempts to join a wrong section).
     InvalidSection,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    #[test]
+    fn any_address_round_trip() -> Result<()> {
+        let addresses = vec![
+            AnyAddress::from(BlobAddress::Public(XorName::random())),
+            AnyAddress::from(MapAddress::Seq {
+                name: XorName::random(),
+                tag: 15_000,
+            }),
+            AnyAddress::from(SequenceAddress::Private {
+                name: XorName::random(),
+                tag: 15_000,
+            }),
+        ];
+        for address in addresses {
+            let formatted = address.to_string();
+            assert_eq!(formatted.parse::<AnyAddress>()?, address);
+        }
+
+        let blob = BlobAddress::Public(XorName::random()).encode_to_zbase32();
+        for invalid in &[
+            format!("safe://chunk/{}", blob),
+            format!("http://blob/{}", blob),
+            "safe://blob".to_string(),
+            "safe://blob/not z-base-32".to_string(),
+        ] {
+            assert!(match invalid.parse::<AnyAddress>() {
+                Err(Error::FailedToParse(_)) => true,
+                _ => false,
+            });
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn store_cost_bytes() {
+        let small = Data::from(Blob::from(PublicBlob::new(vec![1; 10])));
+        assert_eq!(small.store_cost_bytes(), STORE_COST_UNIT_IN_BYTES);
+
+        let large = Data::from(Blob::from(PublicBlob::new(vec![1; 10 * 1024])));
+        let large_cost = large.store_cost_bytes();
+        assert_eq!(large_cost % STORE_COST_UNIT_IN_BYTES, 0);
+        // The serialised Blob is slightly larger than its contents.
+        assert_eq!(large_cost, 11 * STORE_COST_UNIT_IN_BYTES);
+        assert!(large_cost > small.store_cost_bytes());
+    }
+
+    #[test]
+    fn storage_quota() {
+        let mut rng = rand::thread_rng();
+        let owner1 = Keypair::new_ed25519(&mut rng).public_key();
+        let owner2 = Keypair::new_ed25519(&mut rng).public_key();
+
+        let blob = Data::from(Blob::from(PrivateBlob::new(vec![1; 100], owner1)));
+        let map = Data::from(Map::from(UnseqMap::new(XorName::random(), 15_000, owner1)));
+        let mut sequence = Sequence::new_pub(owner2, XorName::random(), 43_000);
+        let _ = sequence.set_owner(owner2);
+        let sequence = Data::from(sequence);
+
+        let mut quota = StorageQuota::new();
+        assert_eq!(quota.add(&blob), Some(blob.serialised_size()));
+        let owner1_usage = blob.serialised_size() + map.serialised_size();
+        assert_eq!(quota.add(&map), Some(owner1_usage));
+        assert_eq!(quota.add(&sequence), Some(sequence.serialised_size()));
+        assert_eq!(quota.usage(&owner1), owner1_usage);
+        assert_eq!(quota.usage(&owner2), sequence.serialised_size());
+
+        let public_blob = Data::from(Blob::from(PublicBlob::new(vec![1; 100])));
+        assert_eq!(quota.add(&public_blob), None);
+
+        assert_eq!(quota.remove(&blob), Some(map.serialised_size()));
+        assert_eq!(quota.remove(&map), Some(0));
+        assert_eq!(quota.usage(&owner1), 0);
+        assert_eq!(quota.usage(&owner2), sequence.serialised_size());
+        assert_eq!(quota.remove(&sequence), Some(0));
+        assert_eq!(quota, StorageQuota::new());
+    }
+
+    #[test]
+    fn filter_owned_by_owner() {
+        let mut rng = rand::thread_rng();
+        let owner = Keypair::new_ed25519(&mut rng).public_key();
+        let other = Keypair::new_ed25519(&mut rng).public_key();
+
+        let mut private_sequence = Sequence::new_private(owner, XorName::random(), 43_000);
+        let _ = private_sequence.set_owner(owner);
+        let mut public_sequence = Sequence::new_pub(owner, XorName::random(), 43_000);
+        let _ = public_sequence.set_owner(owner);
+        let data = vec![
+            Data::from(Blob::from(PrivateBlob::new(vec![1; 10], owner))),
+            Data::from(Blob::from(PrivateBlob::new(vec![1; 10], other))),
+            Data::from(Blob::from(PublicBlob::new(vec![1; 10]))),
+            Data::from(Map::from(UnseqMap::new(XorName::random(), 15_000, other))),
+            Data::from(private_sequence),
+            Data::from(public_sequence),
+        ];
+
+        let owned = filter_owned_by(&data, &owner);
+        assert_eq!(owned, vec![&data[0], &data[4], &data[5]]);
+
+        let deletions: Vec<_> = owned
+            .iter()
+            .filter_map(|data| data.mark_for_deletion())
+            .collect();
+        assert_eq!(deletions.len(), 2);
+        match &deletions[0] {
+            DataCmd::Blob(BlobWrite::DeletePrivate(address)) => assert!(address.is_unpub()),
+            cmd => panic!("Unexpected cmd: {:?}", cmd),
+        }
+        match &deletions[1] {
+            DataCmd::Sequence(SequenceWrite::Delete(address)) => assert!(address.is_private()),
+            cmd => panic!("Unexpected cmd: {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn snapshot_sign_verify() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let owner = Keypair::new_ed25519(&mut rng);
+        let mut sequence = Sequence::new_pub(owner.public_key(), XorName::random(), 43_000);
+        let _ = sequence.set_owner(owner.public_key());
+        let _ = sequence.append(b"value".to_vec());
+        let map = Map::from(UnseqMap::new(XorName::random(), 15_000, owner.public_key()));
+
+        for data in vec![Data::from(sequence), Data::from(map)] {
+            let snapshot = data.sign_snapshot(&owner)?;
+            assert_eq!(snapshot.data, data);
+            snapshot.verify(&owner.public_key())?;
+
+            let bytes = utils::serialise(&snapshot);
+            let restored: SignedSnapshot = unwrap!(bincode::deserialize(&bytes));
+            restored.verify(&owner.public_key())?;
+        }
+
+        let other = Keypair::new_ed25519(&mut rng);
+        let map = Data::from(Map::from(UnseqMap::new(
+            XorName::random(),
+            15_000,
+            owner.public_key(),
+        )));
+        assert_eq!(map.sign_snapshot(&other), Err(Error::AccessDenied));
+        let blob = Data::from(Blob::from(PublicBlob::new(vec![1; 10])));
+        assert_eq!(blob.sign_snapshot(&owner), Err(Error::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_tampering() -> Result<()> {
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng());
+        let mut sequence = Sequence::new_pub(owner.public_key(), XorName::random(), 43_000);
+        let _ = sequence.set_owner(owner.public_key());
+        let _ = sequence.append(b"value".to_vec());
+
+        let mut snapshot = Data::from(sequence.clone()).sign_snapshot(&owner)?;
+        let _ = sequence.append(b"forged".to_vec());
+        snapshot.data = Data::from(sequence);
+        assert_eq!(
+            snapshot.verify(&owner.public_key()),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            snapshot.verify(&Keypair::new_ed25519(&mut rand::thread_rng()).public_key()),
+            Err(Error::InvalidSignature)
+        );
+        Ok(())
+    }
+}
@@ -27,10 +27,10 @@
 //! does not have to pass version numbers for keys, but it still must pass the next version number
 //! while modifying the Map shell.
 
-use crate::{utils, EntryError, Error, PublicKey, Result};
+use crate::{utils, EntryError, Error, PublicKey, Result, TypeTag};
 use hex_fmt::HexFmt;
 use multibase::Decodable;
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStructVariant, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::{btree_map::Entry, BTreeMap, BTreeSet},
     fmt::{self, Debug, Formatter},
@@ -40,12 +40,14 @@ use xor_name::XorName;
 
 /// Map that is unpublished on the network. This data can only be fetched by the owner or
 /// those in the permissions fields with `Permission::Read` access.
-#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub struct SeqData {
     /// Network address.
     address: Address,
     /// Key-Value semantics.
     data: SeqEntries,
+    /// Optional expiry times of entries, keyed by the entry key.
+    expiry: BTreeMap<Vec<u8>, u64>,
     /// Maps an application key to a list of allowed or forbidden actions.
     permissions: BTreeMap<PublicKey, PermissionSet>,
     /// Version should be increased for any changes to Map fields except for data.
@@ -54,6 +56,8 @@ pub struct SeqData {
     ///
     /// Data Handlers in vaults enforce that a mutation request has a valid signature of the owner.
     owner: PublicKey,
+    /// Owner proposed by the current owner, which has not accepted the ownership yet.
+    pending_owner: Option<PublicKey>,
 }
 
 impl Debug for SeqData {
@@ -64,12 +68,14 @@ impl Debug for SeqData {
 
 /// Map that is unpublished on the network. This data can only be fetched by the owner or
 /// those in the permissions fields with `Permission::Read` access.
-#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub struct UnseqData {
     /// Network address.
     address: Address,
     /// Key-Value semantics.
     data: UnseqEntries,
+    /// Optional expiry times of entries, keyed by the entry key.
+    expiry: BTreeMap<Vec<u8>, u64>,
     /// Maps an application key to a list of allowed or forbidden actions.
     permissions: BTreeMap<PublicKey, PermissionSet>,
     /// Version should be increased for any changes to Map fields except for data.
@@ -78,6 +84,8 @@ pub struct UnseqData {
     ///
     /// Data Handlers in vaults enforce that a mutation request has a valid signature of the owner.
     owner: PublicKey,
+    /// Owner proposed by the current owner, which has not accepted the ownership yet.
+    pending_owner: Option<PublicKey>,
 }
 
 impl Debug for UnseqData {
@@ -144,6 +152,9 @@ impl From<Vec<Vec<u8>>> for Values {
 }
 
 /// Set of user permissions.
+///
+/// Every action is denied unless explicitly allowed, e.g.
+/// `PermissionSet::new().allow_all_reads().allow(Action::Insert)`.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 pub struct PermissionSet {
     permissions: BTreeSet<Action>,
@@ -169,6 +180,20 @@ impl PermissionSet {
         self
     }
 
+    /// Allows all the actions reading the data, i.e. `Action::Read`.
+    pub fn allow_all_reads(self) -> Self {
+        self.allow(Action::Read)
+    }
+
+    /// Allows all the actions modifying the data, i.e. inserting, updating and deleting entries,
+    /// and managing permissions.
+    pub fn allow_all_writes(self) -> Self {
+        self.allow(Action::Insert)
+            .allow(Action::Update)
+            .allow(Action::Delete)
+            .allow(Action::ManagePermissions)
+    }
+
     /// Is the given action allowed according to this permission set?
     pub fn is_allowed(&self, action: Action) -> bool {
         self.permissions.contains(&action)
@@ -231,11 +256,13 @@ macro_rules! impl_map {
             /// Returns the shell of this Map (the fields without the data).
             pub fn shell(&self) -> Self {
                 Self {
-                    address: self.address.clone(),
+                    address: self.address,
                     data: BTreeMap::new(),
+                    expiry: BTreeMap::new(),
                     permissions: self.permissions.clone(),
                     version: self.version,
                     owner: self.owner,
+                    pending_owner: self.pending_owner,
                 }
             }
 
@@ -336,7 +363,7 @@ macro_rules! impl_map {
                 true
             }
 
-            /// Changes the owner.
+            /// Changes the owner, discarding any pending owner proposal.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
             /// current version + 1, an error will be returned.
@@ -345,13 +372,58 @@ macro_rules! impl_map {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
 
+                self.pending_owner = None;
                 self.owner = new_owner;
                 self.version = version;
 
                 Ok(())
             }
 
-            /// Changes the owner without performing any validation.
+            /// Returns the proposed owner which has not accepted the ownership yet, if any.
+            pub fn pending_owner(&self) -> Option<&PublicKey> {
+                self.pending_owner.as_ref()
+            }
+
+            /// Proposes `new_owner` as the next owner, replacing any pending proposal.
+            ///
+            /// The ownership is only transferred once the proposed owner accepts it with
+            /// `accept_owner`; until then the current owner stays in effect.
+            ///
+            /// Requires the new `version` of the Map fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn propose_owner(&mut self, new_owner: PublicKey, version: u64) -> Result<()> {
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+
+                self.pending_owner = Some(new_owner);
+                self.version = version;
+
+                Ok(())
+            }
+
+            /// Completes the pending ownership transfer, making `signer` the owner.
+            ///
+            /// Requires the new `version` of the Map fields. If it does not match the
+            /// current version + 1, an error will be returned. Returns
+            /// `Error::AccessDenied` if `signer` is not the proposed owner.
+            pub fn accept_owner(&mut self, signer: PublicKey, version: u64) -> Result<()> {
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+                if self.pending_owner != Some(signer) {
+                    return Err(Error::AccessDenied);
+                }
+
+                self.pending_owner = None;
+                self.owner = signer;
+                self.version = version;
+
+                Ok(())
+            }
+
+            /// Changes the owner without performing any validation, discarding any pending owner
+            /// proposal.
             ///
             /// Requires the new `version` of the Map fields. If it does not match the
             /// current version + 1, an error will be returned.
@@ -364,6 +436,7 @@ macro_rules! impl_map {
                     return false;
                 }
 
+                self.pending_owner = None;
                 self.owner = new_owner;
                 self.version = version;
 
@@ -377,6 +450,64 @@ macro_rules! impl_map {
                     None => false,
                 }
             }
+
+            /// Sets the time after which the entry under `key` is considered expired.
+            ///
+            /// Requires the new `version` of the Map fields. If it does not match the
+            /// current version + 1, an error will be returned. Returns
+            /// `Err(Error::NoSuchEntry)` if there is no such entry.
+            pub fn set_entry_expiry(
+                &mut self,
+                key: &[u8],
+                expires_at: u64,
+                version: u64,
+            ) -> Result<()> {
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+                if !self.data.contains_key(key) {
+                    return Err(Error::NoSuchEntry);
+                }
+                let _ = self.expiry.insert(key.to_vec(), expires_at);
+                self.version = version;
+                Ok(())
+            }
+
+            /// Returns `Some(true)` if the entry under `key` expired at or before `now`,
+            /// `Some(false)` if it hasn't expired or never expires, and `None` if there is no
+            /// such entry.
+            pub fn is_entry_expired(&self, key: &[u8], now: u64) -> Option<bool> {
+                if !self.data.contains_key(key) {
+                    return None;
+                }
+                let expired = match self.expiry.get(key) {
+                    Some(expires_at) => *expires_at <= now,
+                    None => false,
+                };
+                Some(expired)
+            }
+
+            /// Returns the keys of all the entries which expired at or before `now`.
+            pub fn expired_keys(&self, now: u64) -> Vec<Vec<u8>> {
+                self.expiry
+                    .iter()
+                    .filter(|(key, expires_at)| **expires_at <= now && self.data.contains_key(*key))
+                    .map(|(key, _)| key.clone())
+                    .collect()
+            }
+
+            /// Drops the expiry times of entries which no longer exist.
+            fn prune_expiry(&mut self) {
+                let stale: Vec<_> = self
+                    .expiry
+                    .keys()
+                    .filter(|key| !self.data.contains_key(*key))
+                    .cloned()
+                    .collect();
+                for key in stale {
+                    let _ = self.expiry.remove(&key);
+                }
+            }
         }
     };
 }
@@ -384,15 +515,143 @@ macro_rules! impl_map {
 impl_map!(SeqData);
 impl_map!(UnseqData);
 
+/// Serialised layout of `SeqData` and `UnseqData`.
+///
+/// Maps serialised before entry expiry and pending owners were added start with their `Address`,
+/// i.e. the variant index of `Address::Unseq` or `Address::Seq` followed by the name and tag. The
+/// two legacy variants mirror that layout field by field, so those Maps still deserialise, while
+/// the current layout is told apart by the index of the `Current` variant.
+#[derive(Deserialize)]
+enum Format<E> {
+    LegacyUnseq {
+        name: XorName,
+        tag: u64,
+        data: E,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        version: u64,
+        owner: PublicKey,
+    },
+    LegacySeq {
+        name: XorName,
+        tag: u64,
+        data: E,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        version: u64,
+        owner: PublicKey,
+    },
+    Current {
+        address: Address,
+        data: E,
+        expiry: BTreeMap<Vec<u8>, u64>,
+        permissions: BTreeMap<PublicKey, PermissionSet>,
+        version: u64,
+        owner: PublicKey,
+        pending_owner: Option<PublicKey>,
+    },
+}
+
+/// Variant index of `Format::Current`.
+const CURRENT_FORMAT_INDEX: u32 = 2;
+
+macro_rules! impl_map_format {
+    ($flavour:ident, $entries:ty) => {
+        impl Serialize for $flavour {
+            fn serialize<S: Serializer>(
+                &self,
+                serialiser: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                let mut state = serialiser.serialize_struct_variant(
+                    "Format",
+                    CURRENT_FORMAT_INDEX,
+                    "Current",
+                    7,
+                )?;
+                state.serialize_field("address", &self.address)?;
+                state.serialize_field("data", &self.data)?;
+                state.serialize_field("expiry", &self.expiry)?;
+                state.serialize_field("permissions", &self.permissions)?;
+                state.serialize_field("version", &self.version)?;
+                state.serialize_field("owner", &self.owner)?;
+                state.serialize_field("pending_owner", &self.pending_owner)?;
+                state.end()
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $flavour {
+            fn deserialize<D: Deserializer<'de>>(
+                deserialiser: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let data = match Format::<$entries>::deserialize(deserialiser)? {
+                    Format::LegacyUnseq {
+                        name,
+                        tag,
+                        data,
+                        permissions,
+                        version,
+                        owner,
+                    } => Self {
+                        address: Address::Unseq { name, tag },
+                        data,
+                        expiry: BTreeMap::new(),
+                        permissions,
+                        version,
+                        owner,
+                        pending_owner: None,
+                    },
+                    Format::LegacySeq {
+                        name,
+                        tag,
+                        data,
+                        permissions,
+                        version,
+                        owner,
+                    } => Self {
+                        address: Address::Seq { name, tag },
+                        data,
+                        expiry: BTreeMap::new(),
+                        permissions,
+                        version,
+                        owner,
+                        pending_owner: None,
+                    },
+                    Format::Current {
+                        address,
+                        data,
+                        expiry,
+                        permissions,
+                        version,
+                        owner,
+                        pending_owner,
+                    } => Self {
+                        address,
+                        data,
+                        expiry,
+                        permissions,
+                        version,
+                        owner,
+                        pending_owner,
+                    },
+                };
+                Ok(data)
+            }
+        }
+    };
+}
+
+impl_map_format!(SeqData, SeqEntries);
+impl_map_format!(UnseqData, UnseqEntries);
+
 impl UnseqData {
     /// Creates a new unsequenced Map.
     pub fn new(name: XorName, tag: u64, owner: PublicKey) -> Self {
         Self {
             address: Address::Unseq { name, tag },
             data: Default::default(),
+            expiry: Default::default(),
             permissions: Default::default(),
             version: 0,
             owner,
+            pending_owner: None,
         }
     }
 
@@ -407,9 +666,11 @@ impl UnseqData {
         Self {
             address: Address::Unseq { name, tag },
             data,
+            expiry: Default::default(),
             permissions,
             version: 0,
             owner,
+            pending_owner: None,
         }
     }
 
@@ -430,6 +691,7 @@ impl UnseqData {
 
     /// Removes and returns all entries.
     pub fn take_entries(&mut self) -> UnseqEntries {
+        self.expiry.clear();
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
@@ -512,6 +774,7 @@ impl UnseqData {
         }
 
         let _old_data = mem::replace(&mut self.data, new_data);
+        self.prune_expiry();
 
         Ok(())
     }
@@ -524,9 +787,11 @@ impl SeqData {
         Self {
             address: Address::Seq { name, tag },
             data: Default::default(),
+            expiry: Default::default(),
             permissions: Default::default(),
             version: 0,
             owner,
+            pending_owner: None,
         }
     }
 
@@ -541,9 +806,11 @@ impl SeqData {
         Self {
             address: Address::Seq { name, tag },
             data,
+            expiry: Default::default(),
             permissions,
             version: 0,
             owner,
+            pending_owner: None,
         }
     }
 
@@ -564,6 +831,7 @@ impl SeqData {
 
     /// Removes and returns all entries
     pub fn take_entries(&mut self) -> SeqEntries {
+        self.expiry.clear();
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
@@ -658,6 +926,7 @@ impl SeqData {
         }
 
         let _old_data = mem::replace(&mut self.data, new_data);
+        self.prune_expiry();
 
         Ok(())
     }
@@ -691,6 +960,12 @@ impl Kind {
     pub fn is_unseq(self) -> bool {
         !self.is_seq()
     }
+
+    /// Returns `true` if sentried, i.e. if the entries are versioned, so that their updates can be
+    /// made conditional on the current version. This is the case of sequenced Maps only.
+    pub fn is_sentried(self) -> bool {
+        self.is_seq()
+    }
 }
 
 /// Address of an Map.
@@ -721,6 +996,12 @@ impl Address {
         }
     }
 
+    /// Derives an `Address` from `owner` and `nonce`, so that it can be recomputed instead of
+    /// stored.
+    pub fn derive(owner: &PublicKey, nonce: &[u8], tag: u64, kind: Kind) -> Self {
+        Self::from_kind(kind, utils::derive_name(owner, nonce), tag)
+    }
+
     /// Returns the kind.
     pub fn kind(&self) -> Kind {
         match self {
@@ -743,6 +1024,16 @@ impl Address {
         }
     }
 
+    /// Returns the messaging address of the section responsible for the data.
+    pub fn to_section_address(&self) -> crate::Address {
+        crate::Address::Section(*self.name())
+    }
+
+    /// Returns the name registered for the tag, if any.
+    pub fn type_name(&self) -> Option<&'static str> {
+        TypeTag(self.tag()).name()
+    }
+
     /// Returns `true` if sequenced.
     pub fn is_seq(&self) -> bool {
         self.kind().is_seq()
@@ -753,6 +1044,16 @@ impl Address {
         self.kind().is_unseq()
     }
 
+    /// Returns `true` if public. All Map is unpublished, so this is always `false`.
+    pub fn is_pub(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if private. All Map is unpublished, so this is always `true`.
+    pub fn is_private(&self) -> bool {
+        !self.is_pub()
+    }
+
     /// Returns the Address serialised and encoded in z-base-32.
     pub fn encode_to_zbase32(&self) -> String {
         utils::encode(&self)
@@ -815,6 +1116,20 @@ impl Data {
         }
     }
 
+    /// Returns the highest of the data version and, for sequenced data, the entry versions.
+    ///
+    /// Changes to unsequenced entries are not versioned, so they are not reflected.
+    pub fn last_modified_version(&self) -> u64 {
+        match self {
+            Data::Seq(data) => data
+                .entries()
+                .values()
+                .map(|value| value.version)
+                .fold(data.version(), u64::max),
+            Data::Unseq(data) => data.version(),
+        }
+    }
+
     /// Returns all the keys in the data.
     pub fn keys(&self) -> BTreeSet<Vec<u8>> {
         match self {
@@ -892,6 +1207,73 @@ impl Data {
         }
     }
 
+    /// Returns the owner proposed with `propose_owner` which has not accepted yet, if any.
+    pub fn pending_owner(&self) -> Option<&PublicKey> {
+        match self {
+            Data::Seq(data) => data.pending_owner(),
+            Data::Unseq(data) => data.pending_owner(),
+        }
+    }
+
+    /// Proposes `new_owner` as the next owner, replacing any pending proposal.
+    ///
+    /// The ownership is only transferred once the proposed owner accepts it with `accept_owner`;
+    /// until then the current owner stays in effect. Requires the new `version` of the Map fields.
+    pub fn propose_owner(&mut self, new_owner: PublicKey, version: u64) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.propose_owner(new_owner, version),
+            Data::Unseq(data) => data.propose_owner(new_owner, version),
+        }
+    }
+
+    /// Completes the pending ownership transfer, making `signer` the owner.
+    ///
+    /// Requires the new `version` of the Map fields. Returns `Error::AccessDenied` if `signer` is
+    /// not the proposed owner.
+    pub fn accept_owner(&mut self, signer: PublicKey, version: u64) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.accept_owner(signer, version),
+            Data::Unseq(data) => data.accept_owner(signer, version),
+        }
+    }
+
+    /// Returns `Error::InvalidOwners` if the owner is a zero key.
+    pub fn validate_owner(&self) -> Result<()> {
+        if self.owner().is_zero() {
+            Err(Error::InvalidOwners)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the time after which the entry under `key` is considered expired.
+    ///
+    /// Requires the new `version` of the Map fields. If it does not match the current
+    /// version + 1, an error will be returned.
+    pub fn set_entry_expiry(&mut self, key: &[u8], expires_at: u64, version: u64) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.set_entry_expiry(key, expires_at, version),
+            Data::Unseq(data) => data.set_entry_expiry(key, expires_at, version),
+        }
+    }
+
+    /// Returns whether the entry under `key` expired at or before `now`, or `None` if there is no
+    /// such entry.
+    pub fn is_entry_expired(&self, key: &[u8], now: u64) -> Option<bool> {
+        match self {
+            Data::Seq(data) => data.is_entry_expired(key, now),
+            Data::Unseq(data) => data.is_entry_expired(key, now),
+        }
+    }
+
+    /// Returns the keys of all the entries which expired at or before `now`.
+    pub fn expired_keys(&self, now: u64) -> Vec<Vec<u8>> {
+        match self {
+            Data::Seq(data) => data.expired_keys(now),
+            Data::Unseq(data) => data.expired_keys(now),
+        }
+    }
+
     /// Mutates entries (key + value pairs) in bulk.
     pub fn mutate_entries(&mut self, actions: EntryActions, requester: PublicKey) -> Result<()> {
         match self {
@@ -1164,9 +1546,40 @@ impl From<UnseqEntries> for Entries {
 
 #[cfg(test)]
 mod tests {
-    use super::{Address, XorName};
+    use super::{
+        Action, Address, Data, Kind, PermissionSet, SeqData, SeqValue, UnseqData, XorName,
+    };
+    use crate::{utils, Error, PublicKey, Result};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
     use unwrap::unwrap;
 
+    #[test]
+    fn map_permission_set_builder() {
+        let writes = [
+            Action::Insert,
+            Action::Update,
+            Action::Delete,
+            Action::ManagePermissions,
+        ];
+
+        let none = PermissionSet::new();
+        assert!(!none.is_allowed(Action::Read));
+        assert!(writes.iter().all(|action| !none.is_allowed(*action)));
+
+        let reads = PermissionSet::new().allow_all_reads();
+        assert!(reads.is_allowed(Action::Read));
+        assert!(writes.iter().all(|action| !reads.is_allowed(*action)));
+
+        let all = PermissionSet::new().allow_all_reads().allow_all_writes();
+        assert!(all.is_allowed(Action::Read));
+        assert!(writes.iter().all(|action| all.is_allowed(*action)));
+
+        let restricted = all.deny(Action::ManagePermissions);
+        assert!(restricted.is_allowed(Action::Insert));
+        assert!(!restricted.is_allowed(Action::ManagePermissions));
+    }
+
     #[test]
     fn zbase32_encode_decode_map_address() {
         let name = XorName(rand::random());
@@ -1175,4 +1588,201 @@ mod tests {
         let decoded = unwrap!(self::Address::decode_from_zbase32(&encoded));
         assert_eq!(address, decoded);
     }
+
+    #[test]
+    fn map_address_derive() {
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let address = Address::derive(&owner, b"nonce", 15000, Kind::Seq);
+        assert_eq!(address, Address::derive(&owner, b"nonce", 15000, Kind::Seq));
+        assert_eq!(address.kind(), Kind::Seq);
+        assert_eq!(address.tag(), 15000);
+        assert_ne!(
+            address.name(),
+            Address::derive(&owner, b"other nonce", 15000, Kind::Seq).name()
+        );
+    }
+
+    #[test]
+    fn map_address_to_section_address() {
+        let name = XorName::random();
+        let address = Address::Unseq { name, tag: 15000 };
+        let section = address.to_section_address();
+        assert_eq!(section, crate::Address::Section(name));
+        assert_eq!(section.xorname(), *address.name());
+    }
+
+    #[test]
+    fn map_kind_is_sentried() {
+        assert!(Kind::Seq.is_sentried());
+        assert!(!Kind::Unseq.is_sentried());
+    }
+
+    #[test]
+    fn map_address_is_private() {
+        let name = XorName::random();
+        let seq_address = Address::Seq { name, tag: 15000 };
+        assert!(seq_address.is_private());
+        assert!(!seq_address.is_pub());
+
+        let unseq_address = Address::Unseq { name, tag: 15000 };
+        assert!(unseq_address.is_private());
+        assert!(!unseq_address.is_pub());
+    }
+
+    #[test]
+    fn map_entry_expiry() -> Result<()> {
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let mut entries = BTreeMap::new();
+        let _ = entries.insert(b"expired".to_vec(), b"value".to_vec());
+        let _ = entries.insert(b"live".to_vec(), b"value".to_vec());
+        let _ = entries.insert(b"forever".to_vec(), b"value".to_vec());
+        let mut map = Data::Unseq(UnseqData::new_with_data(
+            XorName::random(),
+            15000,
+            entries,
+            BTreeMap::new(),
+            owner,
+        ));
+
+        assert_eq!(
+            map.set_entry_expiry(b"expired", 100, 2),
+            Err(Error::InvalidSuccessor(0))
+        );
+        map.set_entry_expiry(b"expired", 100, 1)?;
+        map.set_entry_expiry(b"live", 300, 2)?;
+        assert_eq!(map.version(), 2);
+        assert_eq!(
+            map.set_entry_expiry(b"missing", 300, 3),
+            Err(Error::NoSuchEntry)
+        );
+
+        let now = 200;
+        assert_eq!(map.is_entry_expired(b"expired", now), Some(true));
+        assert_eq!(map.is_entry_expired(b"live", now), Some(false));
+        assert_eq!(map.is_entry_expired(b"forever", now), Some(false));
+        assert_eq!(map.is_entry_expired(b"missing", now), None);
+        assert_eq!(map.expired_keys(now), vec![b"expired".to_vec()]);
+
+        // The expiry times survive a round trip, so they reach the other replicas.
+        let decoded: Data = unwrap!(bincode::deserialize(&utils::serialise(&map)));
+        assert_eq!(decoded, map);
+        assert_eq!(decoded.expired_keys(now), vec![b"expired".to_vec()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_legacy_format() -> Result<()> {
+        // The layout of the Maps serialised before entry expiry and pending owners were added.
+        #[derive(Serialize)]
+        struct LegacyData<E> {
+            address: Address,
+            data: E,
+            permissions: BTreeMap<PublicKey, PermissionSet>,
+            version: u64,
+            owner: PublicKey,
+        }
+
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let user = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(user, PermissionSet::new().allow(Action::Read));
+        let name = XorName::random();
+
+        let mut unseq_entries = BTreeMap::new();
+        let _ = unseq_entries.insert(b"key".to_vec(), b"value".to_vec());
+        let legacy = LegacyData {
+            address: Address::Unseq { name, tag: 15000 },
+            data: unseq_entries.clone(),
+            permissions: permissions.clone(),
+            version: 3,
+            owner,
+        };
+        let decoded: UnseqData = unwrap!(bincode::deserialize(&utils::serialise(&legacy)));
+        assert_eq!(*decoded.address(), legacy.address);
+        assert_eq!(*decoded.entries(), unseq_entries);
+        assert_eq!(decoded.permissions(), permissions);
+        assert_eq!(decoded.version(), 3);
+        assert_eq!(*decoded.owner(), owner);
+        assert_eq!(decoded.pending_owner(), None);
+        assert_eq!(decoded.expired_keys(u64::MAX), Vec::<Vec<u8>>::new());
+
+        let mut seq_entries = BTreeMap::new();
+        let _ = seq_entries.insert(
+            b"key".to_vec(),
+            SeqValue {
+                data: b"value".to_vec(),
+                version: 0,
+            },
+        );
+        let legacy = LegacyData {
+            address: Address::Seq { name, tag: 15000 },
+            data: seq_entries.clone(),
+            permissions,
+            version: 0,
+            owner,
+        };
+        let decoded: SeqData = unwrap!(bincode::deserialize(&utils::serialise(&legacy)));
+        assert_eq!(*decoded.address(), legacy.address);
+        assert_eq!(*decoded.entries(), seq_entries);
+        assert_eq!(*decoded.owner(), owner);
+
+        // Maps in the current layout round trip.
+        let current: SeqData = unwrap!(bincode::deserialize(&utils::serialise(&decoded)));
+        assert_eq!(current, decoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_owner_transfer() -> Result<()> {
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let new_owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let mut map = Data::Unseq(UnseqData::new(XorName::random(), 15000, owner));
+
+        assert_eq!(
+            map.propose_owner(new_owner, 2),
+            Err(Error::InvalidSuccessor(0))
+        );
+        map.propose_owner(new_owner, 1)?;
+        assert_eq!(map.pending_owner(), Some(&new_owner));
+        assert_eq!(map.owner(), owner);
+        assert_eq!(map.version(), 1);
+
+        // The proposal is part of the replicated Map fields.
+        let replica: Data = unwrap!(bincode::deserialize(&utils::serialise(&map)));
+        assert_eq!(replica.pending_owner(), Some(&new_owner));
+
+        assert_eq!(map.accept_owner(owner, 2), Err(Error::AccessDenied));
+        map.accept_owner(new_owner, 2)?;
+        assert_eq!(map.owner(), new_owner);
+        assert_eq!(map.version(), 2);
+        assert_eq!(map.pending_owner(), None);
+        assert_eq!(map.accept_owner(new_owner, 3), Err(Error::AccessDenied));
+
+        Ok(())
+    }
+
+    #[test]
+    fn map_owner_change_discards_proposal() -> Result<()> {
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let proposee = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let new_owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+
+        let mut map = UnseqData::new(XorName::random(), 15000, owner);
+        map.propose_owner(proposee, 1)?;
+        map.change_owner(new_owner, 2)?;
+        assert_eq!(map.pending_owner(), None);
+        assert_eq!(map.accept_owner(proposee, 3), Err(Error::AccessDenied));
+        assert_eq!(*map.owner(), new_owner);
+
+        let mut map = SeqData::new(XorName::random(), 15000, owner);
+        map.propose_owner(proposee, 1)?;
+        assert!(map.change_owner_without_validation(new_owner, 2));
+        assert_eq!(map.pending_owner(), None);
+        assert_eq!(map.accept_owner(proposee, 3), Err(Error::AccessDenied));
+        assert_eq!(*map.owner(), new_owner);
+
+        Ok(())
+    }
 }
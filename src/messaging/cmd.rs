@@ -7,8 +7,8 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{auth::AuthCmd, data::DataCmd, transfer::TransferCmd, AuthorisationKind};
-use crate::{DebitAgreementProof, XorName};
+use super::{auth::AuthCmd, data::DataCmd, transfer::TransferCmd, AuthorisationKind, CmdError};
+use crate::{DebitAgreementProof, Error, XorName};
 use serde::{Deserialize, Serialize};
 
 /// TODO: docs
@@ -29,7 +29,19 @@ pub enum Cmd {
 }
 
 impl Cmd {
-    /// Returns the type of authorisation needed for the cuest.
+    /// Creates a CmdError containing an error, with the CmdError variant corresponding to the
+    /// Cmd variant.
+    pub fn error(&self, error: Error) -> CmdError {
+        use Cmd::*;
+        match self {
+            Auth(c) => c.error(error),
+            Data { cmd, .. } => cmd.error(error),
+            Transfer(c) => c.error(error),
+        }
+    }
+
+    /// Returns the type of authorisation needed for the cmd, e.g. for a gateway to enforce it before
+    /// forwarding the cmd.
     pub fn authorisation_kind(&self) -> AuthorisationKind {
         use Cmd::*;
         match self {
@@ -14,7 +14,10 @@ use super::{
     sequence::{SequenceRead, SequenceWrite},
     AuthorisationKind, CmdError, QueryResponse,
 };
-use crate::{Error, XorName};
+use crate::{
+    Error, MapEntries, MapPermissionSet, PublicKey, SequenceEntries, SequenceOwner,
+    SequenceUserPermissions, XorName,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -142,3 +145,27 @@ impl fmt::Debug for DataQuery {
         }
     }
 }
+
+/// Entries, current owner and the requester's permissions of a data item, fetched in a single
+/// round-trip.
+#[derive(Hash, Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub enum FullDataView {
+    /// Full view of a Map.
+    Map {
+        /// All entries of the Map.
+        entries: MapEntries,
+        /// Current owner of the Map.
+        owner: PublicKey,
+        /// Permissions of the requester.
+        permissions: MapPermissionSet,
+    },
+    /// Full view of a Sequence.
+    Sequence {
+        /// All entries of the Sequence.
+        entries: SequenceEntries,
+        /// Current owner of the Sequence.
+        owner: SequenceOwner,
+        /// Permissions of the requester.
+        permissions: SequenceUserPermissions,
+    },
+}
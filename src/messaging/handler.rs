@@ -0,0 +1,80 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    Address, Cmd, CmdError, Event, MessageId, NodeCmd, NodeCmdError, NodeEvent, NodeQuery,
+    NodeQueryResponse, Query, QueryResponse,
+};
+
+/// Handler of the `Message` variants, which `Message::dispatch` routes a message to.
+///
+/// Every method does nothing by default, so that only the variants of interest need to be
+/// implemented.
+pub trait MessageHandler {
+    /// Handles a `Message::Cmd`.
+    fn on_cmd(&mut self, _cmd: Cmd, _id: MessageId) {}
+
+    /// Handles a `Message::Query`.
+    fn on_query(&mut self, _query: Query, _id: MessageId) {}
+
+    /// Handles a `Message::Event`.
+    fn on_event(&mut self, _event: Event, _id: MessageId, _correlation_id: MessageId) {}
+
+    /// Handles a `Message::QueryResponse`.
+    fn on_query_response(
+        &mut self,
+        _response: QueryResponse,
+        _id: MessageId,
+        _correlation_id: MessageId,
+        _query_origin: Address,
+    ) {
+    }
+
+    /// Handles a `Message::CmdError`.
+    fn on_cmd_error(
+        &mut self,
+        _error: CmdError,
+        _id: MessageId,
+        _correlation_id: MessageId,
+        _cmd_origin: Address,
+    ) {
+    }
+
+    /// Handles a `Message::NodeCmd`.
+    fn on_node_cmd(&mut self, _cmd: NodeCmd, _id: MessageId) {}
+
+    /// Handles a `Message::NodeCmdError`.
+    fn on_node_cmd_error(
+        &mut self,
+        _error: NodeCmdError,
+        _id: MessageId,
+        _correlation_id: MessageId,
+        _cmd_origin: Address,
+    ) {
+    }
+
+    /// Handles a `Message::NodeEvent`.
+    fn on_node_event(&mut self, _event: NodeEvent, _id: MessageId, _correlation_id: MessageId) {}
+
+    /// Handles a `Message::NodeQuery`.
+    fn on_node_query(&mut self, _query: NodeQuery, _id: MessageId) {}
+
+    /// Handles a `Message::NodeQueryResponse`.
+    fn on_node_query_response(
+        &mut self,
+        _response: NodeQueryResponse,
+        _id: MessageId,
+        _correlation_id: MessageId,
+        _query_origin: Address,
+    ) {
+    }
+
+    /// Handles a `Message::Ack`.
+    fn on_ack(&mut self, _id: MessageId, _correlation_id: MessageId, _msg_origin: Address) {}
+}
@@ -46,6 +46,8 @@ pub enum MapRead {
         /// User to get permissions for.
         user: PublicKey,
     },
+    /// Get Map entries, owner and the requester's permissions in one go.
+    GetFull(Address),
 }
 
 /// TODO: docs
@@ -100,6 +102,7 @@ impl MapRead {
             ListValues(_) => QueryResponse::ListMapValues(Err(error)),
             ListPermissions(_) => QueryResponse::ListMapPermissions(Err(error)),
             ListUserPermissions { .. } => QueryResponse::ListMapUserPermissions(Err(error)),
+            GetFull(_) => QueryResponse::GetFull(Err(error)),
         }
     }
 
@@ -115,7 +118,8 @@ impl MapRead {
             | ListKeys(_)
             | ListValues(_)
             | ListPermissions(_)
-            | ListUserPermissions { .. } => AuthorisationKind::Data(DataAuthKind::PrivateRead),
+            | ListUserPermissions { .. }
+            | GetFull(_) => AuthorisationKind::Data(DataAuthKind::PrivateRead),
         }
     }
 
@@ -131,7 +135,8 @@ impl MapRead {
             | ListKeys(ref address)
             | ListValues(ref address)
             | ListPermissions(ref address)
-            | ListUserPermissions { ref address, .. } => *address.name(),
+            | ListUserPermissions { ref address, .. }
+            | GetFull(ref address) => *address.name(),
         }
     }
 }
@@ -152,6 +157,7 @@ impl fmt::Debug for MapRead {
                 ListValues(_) => "ListMapValues",
                 ListPermissions(_) => "ListMapPermissions",
                 ListUserPermissions { .. } => "ListMapUserPermissions",
+                GetFull(_) => "GetFullMap",
             }
         )
     }
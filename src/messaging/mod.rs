@@ -13,8 +13,10 @@ mod blob;
 mod cmd;
 mod data;
 mod duty;
+mod handler;
 mod map;
 mod network;
+mod priority;
 mod query;
 mod sequence;
 mod transfer;
@@ -24,27 +26,58 @@ pub use self::{
     auth::{AuthCmd, AuthQuery},
     blob::{BlobRead, BlobWrite},
     cmd::Cmd,
-    data::{DataCmd, DataQuery},
+    data::{DataCmd, DataQuery, FullDataView},
     duty::{AdultDuties, Duty, ElderDuties, NodeDuties},
+    handler::MessageHandler,
     map::{MapRead, MapWrite},
     network::*,
+    priority::{PrioritizedEnvelope, Priority},
     query::Query,
     sequence::{SequenceRead, SequenceWrite},
-    transfer::{TransferCmd, TransferQuery},
+    transfer::{HistoryChunk, HistoryReassembler, TransferCmd, TransferQuery},
 };
 use crate::{
-    errors::ErrorDebug, utils, AppPermissions, Blob, BlsProof, DebitAgreementProof, Error, Map,
-    MapEntries, MapPermissionSet, MapValue, MapValues, Money, Proof, PublicKey, ReplicaEvent,
-    ReplicaPublicKeySet, Result, Sequence, SequenceEntries, SequenceEntry, SequenceOwner,
-    SequencePermissions, SequenceUserPermissions, Signature, TransferValidated,
+    errors::ErrorDebug, utils, AppPermissions, Blob, BlsProof, ClientFullId, DebitAgreementProof,
+    Ed25519Proof, Error, Keypair, Map, MapEntries, MapPermissionSet, MapValue, MapValues, Money,
+    NodeKeypairs, Proof, PublicKey, ReplicaEvent, ReplicaPublicKeySet, Result, Sequence,
+    SequenceEntries, SequenceEntry, SequenceOwner, SequencePermissions, SequenceUserPermissions,
+    Signature, TransferValidated,
 };
 use serde::{Deserialize, Serialize};
+use signature::Verifier;
 use std::{
     collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
-    fmt,
+    fmt, mem,
+    str::FromStr,
 };
-use xor_name::XorName;
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// Maximum number of proxies which can relay a message, enforced by `MsgEnvelope::try_with_proxy`.
+pub const MAX_PROXIES: usize = 8;
+
+/// Maximum size in bytes of a serialised envelope restored by `MsgEnvelope::decompress`.
+pub const MAX_ENVELOPE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Serialised envelopes larger than this many bytes are compressed by `MsgEnvelope::compress`.
+const COMPRESSION_THRESHOLD: usize = 1024;
+/// Header byte of an envelope serialised by `MsgEnvelope::compress` without compression.
+const UNCOMPRESSED: u8 = 0;
+/// Header byte of an envelope serialised and LZ4-compressed by `MsgEnvelope::compress`.
+const LZ4_COMPRESSED: u8 = 1;
+
+/// Estimated serialised size of a response or event without a variable-size payload, e.g. an
+/// error, a key or an amount of money.
+const APPROX_FIXED_SIZE: usize = 64;
+/// Estimated serialised size of a single Map or Sequence entry, key, value or permission.
+const APPROX_ENTRY_SIZE: usize = 128;
+/// Estimated serialised size of a single transfer event, with its signatures.
+const APPROX_EVENT_SIZE: usize = 512;
+/// Estimated serialised size of a transfer, without its memo.
+const APPROX_TRANSFER_SIZE: usize = 128;
+/// Estimated serialised size of a single signature or signature share.
+const APPROX_SIGNATURE_SIZE: usize = 128;
+
 ///
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -64,20 +97,221 @@ impl MsgEnvelope {
         self.message.id()
     }
 
-    /// This is not quite good.
-    /// It does work for the cases we have,
-    /// but it does so without being clearly robust/flexible.
-    /// So, needs some improvement..
+    /// Verifies the whole chain of signatures: the origin's over the message, then each proxy's
+    /// over the envelope as it was when that proxy signed it, i.e. without that proxy and the
+    /// ones which followed it.
+    ///
+    /// Envelopes relayed by more than `MAX_PROXIES` proxies are invalid, and rejected before
+    /// verifying any signature.
     pub fn verify(&self) -> bool {
-        let data = if self.proxies.is_empty() {
-            utils::serialise(&self.message)
+        if self.proxies.len() > MAX_PROXIES {
+            return false;
+        }
+        if self
+            .origin
+            .verify_against(&utils::serialise(&self.message))
+            .is_err()
+        {
+            return false;
+        }
+        self.verify_proxies()
+    }
+
+    /// Verifies a burst of envelopes like `verify`, returning `Err(Error::TooManyProxies)` for
+    /// each one relayed by more than `MAX_PROXIES` proxies, and `Err(Error::InvalidSignature)`
+    /// for each other invalid one.
+    ///
+    /// The Ed25519 signatures of the origins are verified in one batch, which is faster than one
+    /// by one. Should the batch fail, they are verified one by one to tell which are invalid.
+    pub fn verify_many(envelopes: &[MsgEnvelope]) -> Vec<Result<()>> {
+        let messages: Vec<_> = envelopes
+            .iter()
+            .map(|envelope| utils::serialise(&envelope.message))
+            .collect();
+        let mut origins_valid = vec![false; envelopes.len()];
+        let mut batch = Vec::new();
+        for (index, envelope) in envelopes.iter().enumerate() {
+            if envelope.proxies.len() > MAX_PROXIES {
+                continue;
+            }
+            match envelope.ed25519_origin() {
+                Some(proof) => batch.push((index, proof)),
+                None => {
+                    origins_valid[index] = envelope.origin.verify_against(&messages[index]).is_ok()
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            let batch_messages: Vec<&[u8]> = batch
+                .iter()
+                .map(|(index, _)| &messages[*index][..])
+                .collect();
+            let signatures: Vec<_> = batch.iter().map(|(_, proof)| proof.signature).collect();
+            let public_keys: Vec<_> = batch.iter().map(|(_, proof)| proof.public_key).collect();
+            if ed25519_dalek::verify_batch(&batch_messages, &signatures, &public_keys).is_ok() {
+                for (index, _) in &batch {
+                    origins_valid[*index] = true;
+                }
+            } else {
+                for (index, proof) in &batch {
+                    origins_valid[*index] = proof
+                        .public_key
+                        .verify(&messages[*index], &proof.signature)
+                        .is_ok();
+                }
+            }
+        }
+
+        envelopes
+            .iter()
+            .zip(origins_valid)
+            .map(|(envelope, origin_valid)| {
+                if envelope.proxies.len() > MAX_PROXIES {
+                    Err(Error::TooManyProxies)
+                } else if origin_valid && envelope.verify_proxies() {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidSignature)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the Ed25519 proof of the origin, if it signed with an Ed25519 key.
+    fn ed25519_origin(&self) -> Option<&Ed25519Proof> {
+        match &self.origin {
+            MsgSender::Client(Proof::Ed25519(proof))
+            | MsgSender::Node {
+                proof: Proof::Ed25519(proof),
+                ..
+            } => Some(proof),
+            _ => None,
+        }
+    }
+
+    /// Verifies each proxy signature over the envelope as it was when the proxy relayed it.
+    fn verify_proxies(&self) -> bool {
+        let mut signed = MsgEnvelope {
+            message: self.message.clone(),
+            origin: self.origin.clone(),
+            proxies: Vec::with_capacity(self.proxies.len()),
+        };
+        for proxy in &self.proxies {
+            if proxy.verify_against(&utils::serialise(&signed)).is_err() {
+                return false;
+            }
+            signed.proxies.push(proxy.clone());
+        }
+        true
+    }
+
+    /// Serialises the envelope for transport, compressing it with LZ4 if it is larger than
+    /// `COMPRESSION_THRESHOLD` bytes.
+    ///
+    /// The first byte of the output tells whether the rest is compressed.
+    pub fn compress(&self) -> Result<Vec<u8>> {
+        let serialised = utils::serialise(self);
+        let (header, payload) = if serialised.len() > COMPRESSION_THRESHOLD {
+            (LZ4_COMPRESSED, lz4_flex::compress_prepend_size(&serialised))
         } else {
-            let mut msg = self.clone();
-            let _ = msg.proxies.pop();
-            utils::serialise(&msg)
+            (UNCOMPRESSED, serialised)
+        };
+        let mut bytes = Vec::with_capacity(payload.len() + 1);
+        bytes.push(header);
+        bytes.extend(payload);
+        Ok(bytes)
+    }
+
+    /// Restores an envelope serialised by `compress`.
+    ///
+    /// Returns `Error::FailedToParse` if the bytes are corrupt or have an unknown header, and
+    /// `Error::ExceededSize` if the decompressed envelope would be larger than
+    /// `MAX_ENVELOPE_SIZE`.
+    pub fn decompress(bytes: &[u8]) -> Result<MsgEnvelope> {
+        match bytes.split_first() {
+            Some((&UNCOMPRESSED, payload)) => utils::deserialise(payload),
+            Some((&LZ4_COMPRESSED, payload)) => {
+                // The size is prepended as a little-endian `u32`. It is checked before
+                // decompressing, as that allocates this many bytes upfront.
+                if payload.len() < 4 {
+                    return Err(Error::FailedToParse(
+                        "Missing decompressed envelope size".to_string(),
+                    ));
+                }
+                let (size, compressed) = payload.split_at(4);
+                let size = u32::from_le_bytes([size[0], size[1], size[2], size[3]]) as usize;
+                if size > MAX_ENVELOPE_SIZE {
+                    return Err(Error::ExceededSize);
+                }
+                let serialised = lz4_flex::decompress(compressed, size)
+                    .map_err(|error| Error::FailedToParse(error.to_string()))?;
+                utils::deserialise(&serialised)
+            }
+            Some((header, _)) => Err(Error::FailedToParse(format!(
+                "Unknown envelope compression header {}",
+                header
+            ))),
+            None => Err(Error::FailedToParse("Empty envelope".to_string())),
+        }
+    }
+
+    /// Returns the priority with which a node should process the envelope.
+    pub fn processing_priority(&self) -> Priority {
+        use Message::*;
+        match self.message {
+            Cmd { .. } | Query { .. } => Priority::Low,
+            NodeCmd { .. } | NodeQuery { .. } => Priority::Normal,
+            Event { .. }
+            | QueryResponse { .. }
+            | CmdError { .. }
+            | NodeEvent { .. }
+            | NodeCmdError { .. }
+            | NodeQueryResponse { .. }
+            | Ack { .. } => Priority::High,
+        }
+    }
+
+    /// Creates an envelope of `message` sent by the client `id`, signed by it.
+    pub fn for_client(message: Message, id: &ClientFullId) -> MsgEnvelope {
+        let proof = id.proof(&utils::serialise(&message));
+        MsgEnvelope {
+            message,
+            origin: MsgSender::Client(proof),
+            proxies: vec![],
+        }
+    }
+
+    /// Creates an envelope of `message` sent by a node performing `duty`, signed with its BLS
+    /// keypair share if any, else its Ed25519 keypair.
+    pub fn for_node(message: Message, duty: Duty, keypairs: &NodeKeypairs) -> MsgEnvelope {
+        let proof = keypairs.proof(&utils::serialise(&message));
+        MsgEnvelope {
+            message,
+            origin: MsgSender::Node { duty, proof },
+            proxies: vec![],
+        }
+    }
+
+    /// Creates an envelope acknowledging this message, signed by the node which handled it, so
+    /// that the origin can confirm delivery.
+    ///
+    /// Returns `Error::InvalidOperation` if this message is itself an acknowledgement.
+    pub fn ack(&self, signer: &Keypair, duty: Duty) -> Result<MsgEnvelope> {
+        if let Message::Ack { .. } = self.message {
+            return Err(Error::InvalidOperation);
+        }
+        let message = Message::Ack {
+            id: MessageId::new(),
+            correlation_id: self.id(),
+            msg_origin: self.origin.address(),
         };
-        let signer = self.most_recent_sender();
-        signer.id().verify(&signer.signature(), data).is_ok()
+        let proof = signer.proof(&utils::serialise(&message));
+        Ok(MsgEnvelope {
+            message,
+            origin: MsgSender::Node { duty, proof },
+            proxies: vec![],
+        })
     }
 
     /// The proxy would first sign the MsgEnvelope,
@@ -89,6 +323,20 @@ impl MsgEnvelope {
         clone
     }
 
+    /// Like `with_proxy`, but returns `Error::TooManyProxies` if the envelope would then have been
+    /// relayed by more than `MAX_PROXIES` proxies, e.g. because of a routing loop.
+    pub fn try_with_proxy(&self, proxy: MsgSender) -> Result<MsgEnvelope> {
+        if self.proxies.len() >= MAX_PROXIES {
+            return Err(Error::TooManyProxies);
+        }
+        Ok(self.with_proxy(proxy))
+    }
+
+    /// Returns the proxies which relayed the message, in the order they did.
+    pub fn proxy_chain(&self) -> &[MsgSender] {
+        &self.proxies
+    }
+
     ///
     pub fn most_recent_sender(&self) -> &MsgSender {
         match self.proxies.last() {
@@ -105,16 +353,25 @@ impl MsgEnvelope {
             Cmd { cmd, .. } => self.cmd_dst(cmd),
             Query { query, .. } => Section(query.dst_address()),
             Event { event, .. } => Client(event.dst_address()), // TODO: needs the correct client address
-            QueryResponse { query_origin, .. } => query_origin.clone(),
-            CmdError { cmd_origin, .. } => cmd_origin.clone(),
+            QueryResponse { query_origin, .. } => *query_origin,
+            CmdError { cmd_origin, .. } => *cmd_origin,
             NodeCmd { cmd, .. } => cmd.dst_address(),
             NodeEvent { event, .. } => event.dst_address(),
             NodeQuery { query, .. } => query.dst_address(),
-            NodeCmdError { cmd_origin, .. } => cmd_origin.clone(),
-            NodeQueryResponse { query_origin, .. } => query_origin.clone(),
+            NodeCmdError { cmd_origin, .. } => *cmd_origin,
+            NodeQueryResponse { query_origin, .. } => *query_origin,
+            Ack { msg_origin, .. } => *msg_origin,
         }
     }
 
+    /// All the destinations of the message.
+    ///
+    /// Broadcast-style messages would return every recipient here. None of the current messages
+    /// are broadcast, so this is the single `destination()` for all of them.
+    pub fn destinations(&self) -> Vec<Address> {
+        vec![self.destination()]
+    }
+
     fn cmd_dst(&self, cmd: &Cmd) -> Address {
         use Address::*;
         use Cmd::*;
@@ -208,10 +465,15 @@ impl MsgSender {
             Section { proof, .. } => proof.signature(),
         }
     }
+
+    /// Verifies that the sender's signature is valid for `data`.
+    pub fn verify_against(&self, data: &[u8]) -> Result<()> {
+        self.id().verify(&self.signature(), data)
+    }
 }
 
 ///
-#[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Address {
     ///
     Client(XorName),
@@ -229,6 +491,91 @@ impl Address {
             Client(xorname) | Node(xorname) | Section(xorname) => *xorname,
         }
     }
+
+    /// Returns `true` if this is the address of a Client.
+    pub fn is_client(&self) -> bool {
+        self.as_client().is_some()
+    }
+
+    /// Returns `true` if this is the address of a Node.
+    pub fn is_node(&self) -> bool {
+        self.as_node().is_some()
+    }
+
+    /// Returns `true` if this is the address of a Section.
+    pub fn is_section(&self) -> bool {
+        self.as_section().is_some()
+    }
+
+    /// Returns the name of the Client, if this is the address of a Client.
+    pub fn as_client(&self) -> Option<XorName> {
+        match self {
+            Address::Client(xorname) => Some(*xorname),
+            Address::Node(_) | Address::Section(_) => None,
+        }
+    }
+
+    /// Returns the name of the Node, if this is the address of a Node.
+    pub fn as_node(&self) -> Option<XorName> {
+        match self {
+            Address::Node(xorname) => Some(*xorname),
+            Address::Client(_) | Address::Section(_) => None,
+        }
+    }
+
+    /// Returns the name of the Section, if this is the address of a Section.
+    pub fn as_section(&self) -> Option<XorName> {
+        match self {
+            Address::Section(xorname) => Some(*xorname),
+            Address::Client(_) | Address::Node(_) => None,
+        }
+    }
+}
+
+/// Formats as `<kind>:<hex name>`, e.g. `section:0a1b..`, which `FromStr` parses back.
+impl fmt::Display for Address {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            Address::Client(_) => "client",
+            Address::Node(_) => "node",
+            Address::Section(_) => "section",
+        };
+        write!(formatter, "{}:{}", kind, hex::encode(self.xorname().0))
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Parses an address formatted by `Display`.
+    ///
+    /// Returns `Error::FailedToParse` if the kind prefix is unknown or the name is not
+    /// `XOR_NAME_LEN` bytes of hex.
+    fn from_str(value: &str) -> Result<Self> {
+        let mut parts = value.splitn(2, ':');
+        let kind = parts.next().unwrap_or_default();
+        let name = parts
+            .next()
+            .ok_or_else(|| Error::FailedToParse(format!("Missing address kind in {:?}", value)))?;
+        let bytes = hex::decode(name).map_err(|error| Error::FailedToParse(error.to_string()))?;
+        if bytes.len() != XOR_NAME_LEN {
+            return Err(Error::FailedToParse(format!(
+                "Invalid address name length {}",
+                bytes.len()
+            )));
+        }
+        let mut xorname = XorName([0; XOR_NAME_LEN]);
+        xorname.0.copy_from_slice(&bytes);
+        match kind {
+            "client" => Ok(Address::Client(xorname)),
+            "node" => Ok(Address::Node(xorname)),
+            "section" => Ok(Address::Section(xorname)),
+            _ => Err(Error::FailedToParse(format!(
+                "Unknown address kind {:?}",
+                kind
+            ))),
+        }
+    }
 }
 
 ///
@@ -327,6 +674,15 @@ pub enum Message {
         /// The sender of the causing query.
         query_origin: Address,
     },
+    /// Acknowledgement that a message was successfully handled.
+    Ack {
+        /// Message ID.
+        id: MessageId,
+        /// ID of the acknowledged message.
+        correlation_id: MessageId,
+        /// The sender of the acknowledged message.
+        msg_origin: Address,
+    },
 }
 
 impl Message {
@@ -342,7 +698,93 @@ impl Message {
             | Self::NodeEvent { id, .. }
             | Self::NodeQuery { id, .. }
             | Self::NodeCmdError { id, .. }
-            | Self::NodeQueryResponse { id, .. } => *id,
+            | Self::NodeQueryResponse { id, .. }
+            | Self::Ack { id, .. } => *id,
+        }
+    }
+
+    /// Gets a mutable reference to the message ID.
+    fn id_mut(&mut self) -> &mut MessageId {
+        match self {
+            Self::Cmd { id, .. }
+            | Self::Query { id, .. }
+            | Self::Event { id, .. }
+            | Self::QueryResponse { id, .. }
+            | Self::CmdError { id, .. }
+            | Self::NodeCmd { id, .. }
+            | Self::NodeEvent { id, .. }
+            | Self::NodeQuery { id, .. }
+            | Self::NodeCmdError { id, .. }
+            | Self::NodeQueryResponse { id, .. }
+            | Self::Ack { id, .. } => id,
+        }
+    }
+
+    /// Returns the address the response or error to this message is to be sent to, given the
+    /// `origin` of the message, or `None` if the message is not a request, i.e. not a cmd or a
+    /// query.
+    pub fn reply_address(&self, origin: &MsgSender) -> Option<Address> {
+        match self {
+            Self::Cmd { .. }
+            | Self::Query { .. }
+            | Self::NodeCmd { .. }
+            | Self::NodeQuery { .. } => Some(origin.address()),
+            Self::Event { .. }
+            | Self::QueryResponse { .. }
+            | Self::CmdError { .. }
+            | Self::NodeEvent { .. }
+            | Self::NodeCmdError { .. }
+            | Self::NodeQueryResponse { .. }
+            | Self::Ack { .. } => None,
+        }
+    }
+
+    /// Routes the message to the `handler` method for its variant.
+    pub fn dispatch(self, handler: &mut impl MessageHandler) {
+        match self {
+            Self::Cmd { cmd, id } => handler.on_cmd(cmd, id),
+            Self::Query { query, id } => handler.on_query(query, id),
+            Self::Event {
+                event,
+                id,
+                correlation_id,
+            } => handler.on_event(event, id, correlation_id),
+            Self::QueryResponse {
+                response,
+                id,
+                correlation_id,
+                query_origin,
+            } => handler.on_query_response(response, id, correlation_id, query_origin),
+            Self::CmdError {
+                error,
+                id,
+                correlation_id,
+                cmd_origin,
+            } => handler.on_cmd_error(error, id, correlation_id, cmd_origin),
+            Self::NodeCmd { cmd, id } => handler.on_node_cmd(cmd, id),
+            Self::NodeCmdError {
+                error,
+                id,
+                correlation_id,
+                cmd_origin,
+            } => handler.on_node_cmd_error(error, id, correlation_id, cmd_origin),
+            Self::NodeEvent {
+                event,
+                id,
+                correlation_id,
+            } => handler.on_node_event(event, id, correlation_id),
+            Self::NodeQuery { query, id } => handler.on_node_query(query, id),
+            Self::NodeQueryResponse {
+                response,
+                id,
+                correlation_id,
+                query_origin,
+            } => handler.on_node_query_response(response, id, correlation_id, query_origin),
+            Self::Ack {
+                id,
+                correlation_id,
+                msg_origin,
+            } => handler.on_ack(id, correlation_id, msg_origin),
         }
     }
 }
@@ -355,11 +797,46 @@ impl Message {
 #[derive(Ord, PartialOrd, Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub struct MessageId(pub XorName);
 
+/// Length of the timestamp prefix of a timestamped `MessageId`.
+const TIMESTAMP_LEN: usize = 8;
+/// Marker following the timestamp prefix, used to tell timestamped ids apart from random ones.
+const TIMESTAMP_MARKER: &[u8] = b"tsid";
+
 impl MessageId {
     /// Generates a new `MessageId` with random content.
     pub fn new() -> Self {
         Self(XorName::random())
     }
+
+    /// Derives a `MessageId` from the content of `message`, i.e. everything but its own id, so that
+    /// a message resent as is gets the same id and is deduplicated by the nodes.
+    pub fn from_content(message: &Message) -> Self {
+        let mut content = message.clone();
+        *content.id_mut() = Self(XorName([0; XOR_NAME_LEN]));
+        Self(XorName(tiny_keccak::sha3_256(&utils::serialise(&content))))
+    }
+
+    /// Generates a new `MessageId` prefixed with the given timestamp (in milliseconds), the rest
+    /// being random. Such ids are ordered by their timestamp, which is handy when reading logs.
+    pub fn new_timestamped(now_millis: u64) -> Self {
+        let mut name = XorName::random();
+        name.0[..TIMESTAMP_LEN].copy_from_slice(&now_millis.to_be_bytes());
+        name.0[TIMESTAMP_LEN..TIMESTAMP_LEN + TIMESTAMP_MARKER.len()]
+            .copy_from_slice(TIMESTAMP_MARKER);
+        Self(name)
+    }
+
+    /// Returns the timestamp (in milliseconds) of an id created with `new_timestamped`, or `None`
+    /// if the id does not carry one.
+    pub fn timestamp(&self) -> Option<u64> {
+        let bytes = &(self.0).0;
+        if &bytes[TIMESTAMP_LEN..TIMESTAMP_LEN + TIMESTAMP_MARKER.len()] != TIMESTAMP_MARKER {
+            return None;
+        }
+        let mut timestamp = [0; TIMESTAMP_LEN];
+        timestamp.copy_from_slice(&bytes[..TIMESTAMP_LEN]);
+        Some(u64::from_be_bytes(timestamp))
+    }
 }
 
 impl Default for MessageId {
@@ -379,6 +856,23 @@ pub enum CmdError {
     Transfer(TransferError),
 }
 
+impl CmdError {
+    /// Creates the error response to `cmd`, with the variant corresponding to the cmd kind.
+    pub fn for_cmd(cmd: &Cmd, error: Error) -> Self {
+        cmd.error(error)
+    }
+
+    /// Returns the HTTP status code best describing the error, e.g. for a REST gateway.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            CmdError::Auth(error)
+            | CmdError::Data(error)
+            | CmdError::Transfer(TransferError::TransferValidation(error))
+            | CmdError::Transfer(TransferError::TransferRegistration(error)) => error.http_status(),
+        }
+    }
+}
+
 ///
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TransferError {
@@ -390,6 +884,9 @@ pub enum TransferError {
 
 /// Events from the network that
 /// are pushed to the client.
+///
+/// Variants are serialised by their position, so new variants must only be appended, and
+/// existing ones never reordered or removed.
 #[allow(clippy::large_enum_variant, clippy::type_complexity)]
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Event {
@@ -407,6 +904,7 @@ pub enum Event {
     /// This is a temporary variant, until
     /// SignatureAccumulation has been broken out
     /// to its own crate, and can be used at client.
+    /// The proof can be accumulated with `TransferAccumulator`.
     TransferDebitAgreementReached {
         /// This is the client id.
         /// A client can fhave any number of accounts.
@@ -425,6 +923,24 @@ impl Event {
             TransferDebitAgreementReached { client, .. } => *client,
         }
     }
+
+    /// Returns an estimate of the serialised size of the event, e.g. for flow control, without
+    /// serialising it.
+    ///
+    /// The memo of the transfer and the key set of the Replicas are counted from their sizes.
+    pub fn approx_size(&self) -> usize {
+        use Event::*;
+        // Both carry the signature of the sender and one of the Replicas, or a share of it.
+        let (signed_transfer, replicas) = match self {
+            TransferValidated { event, .. } => (&event.signed_transfer, &event.replicas),
+            TransferDebitAgreementReached { proof, .. } => {
+                (&proof.signed_transfer, &proof.replica_key)
+            }
+        };
+        let memo = signed_transfer.transfer.memo.as_ref().map_or(0, Vec::len);
+        let key_set = (replicas.threshold() + 1) * threshold_crypto::PK_SIZE;
+        APPROX_FIXED_SIZE + APPROX_TRANSFER_SIZE + memo + 2 * APPROX_SIGNATURE_SIZE + key_set
+    }
 }
 
 /// Query responses from the network.
@@ -462,6 +978,8 @@ pub enum QueryResponse {
     //
     /// Get Sequence.
     GetSequence(Result<Sequence>),
+    /// Get Sequence shell, i.e. its metadata without the entries.
+    GetSequenceShell(Result<Sequence>),
     /// Get Sequence owners.
     GetSequenceOwner(Result<SequenceOwner>),
     /// Get Sequence entries from a range.
@@ -473,6 +991,11 @@ pub enum QueryResponse {
     /// Get Sequence permissions for a user.
     GetSequenceUserPermissions(Result<SequenceUserPermissions>),
     //
+    // ===== Map and Sequence =====
+    //
+    /// Get entries, owner and the requester's permissions of a Map or Sequence.
+    GetFull(Result<FullDataView>),
+    //
     // ===== Money =====
     //
     /// Get replica keys
@@ -481,6 +1004,8 @@ pub enum QueryResponse {
     GetBalance(Result<Money>),
     /// Get key transfer history.
     GetHistory(Result<Vec<ReplicaEvent>>),
+    /// Get a chunk of a key transfer history too large for a single response.
+    GetHistoryChunk(Result<HistoryChunk>),
     //
     // ===== Account =====
     //
@@ -493,7 +1018,148 @@ pub enum QueryResponse {
     ListAuthKeysAndVersion(Result<(BTreeMap<PublicKey, AppPermissions>, u64)>),
 }
 
+impl QueryResponse {
+    /// Returns `true` if both responses are of the same variant and either both succeeded or both
+    /// failed. The actual data and error details are not compared.
+    pub fn same_shape(&self, other: &QueryResponse) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+            && self.error().is_some() == other.error().is_some()
+    }
+
+    /// Returns a description of the response which is safe to log: only the variant, whether it
+    /// succeeded or failed and, on success, the size of the serialised payload. The payload
+    /// itself, e.g. account ciphertext or signatures, is never included.
+    pub fn log_safe(&self) -> String {
+        use QueryResponse::*;
+        match self {
+            GetBlob(res) => log_safe("GetBlob", res),
+            GetMap(res) => log_safe("GetMap", res),
+            GetMapShell(res) => log_safe("GetMapShell", res),
+            GetMapVersion(res) => log_safe("GetMapVersion", res),
+            ListMapEntries(res) => log_safe("ListMapEntries", res),
+            ListMapKeys(res) => log_safe("ListMapKeys", res),
+            ListMapValues(res) => log_safe("ListMapValues", res),
+            ListMapUserPermissions(res) => log_safe("ListMapUserPermissions", res),
+            ListMapPermissions(res) => log_safe("ListMapPermissions", res),
+            GetMapValue(res) => log_safe("GetMapValue", res),
+            GetSequence(res) => log_safe("GetSequence", res),
+            GetSequenceShell(res) => log_safe("GetSequenceShell", res),
+            GetSequenceOwner(res) => log_safe("GetSequenceOwner", res),
+            GetSequenceRange(res) => log_safe("GetSequenceRange", res),
+            GetSequenceLastEntry(res) => log_safe("GetSequenceLastEntry", res),
+            GetSequencePermissions(res) => log_safe("GetSequencePermissions", res),
+            GetSequenceUserPermissions(res) => log_safe("GetSequenceUserPermissions", res),
+            GetFull(res) => log_safe("GetFull", res),
+            GetReplicaKeys(res) => log_safe("GetReplicaKeys", res),
+            GetBalance(res) => log_safe("GetBalance", res),
+            GetHistory(res) => log_safe("GetHistory", res),
+            GetHistoryChunk(res) => log_safe("GetHistoryChunk", res),
+            GetAccount(res) => log_safe("GetAccount", res),
+            ListAuthKeysAndVersion(res) => log_safe("ListAuthKeysAndVersion", res),
+        }
+    }
+
+    /// Returns an estimate of the serialised size of the response, e.g. for flow control, without
+    /// serialising it.
+    ///
+    /// Byte payloads, such as Blobs, are counted exactly, while collections are estimated from
+    /// their number of items.
+    pub fn approx_size(&self) -> usize {
+        use QueryResponse::*;
+        let items = match self {
+            GetBlob(Ok(blob)) => return APPROX_FIXED_SIZE + blob.value().len(),
+            GetAccount(Ok((account, _))) => return APPROX_FIXED_SIZE + account.len(),
+            GetHistory(Ok(events)) => {
+                return APPROX_FIXED_SIZE + events.len() * APPROX_EVENT_SIZE;
+            }
+            GetHistoryChunk(Ok(chunk)) => {
+                return APPROX_FIXED_SIZE + chunk.events.len() * APPROX_EVENT_SIZE;
+            }
+            GetMap(Ok(map)) | GetMapShell(Ok(map)) => match map {
+                Map::Seq(data) => data.entries().len(),
+                Map::Unseq(data) => data.entries().len(),
+            },
+            ListMapEntries(Ok(MapEntries::Seq(entries))) => entries.len(),
+            ListMapEntries(Ok(MapEntries::Unseq(entries))) => entries.len(),
+            ListMapKeys(Ok(keys)) => keys.len(),
+            ListMapValues(Ok(MapValues::Seq(values))) => values.len(),
+            ListMapValues(Ok(MapValues::Unseq(values))) => values.len(),
+            ListMapPermissions(Ok(permissions)) => permissions.len(),
+            GetSequence(Ok(sequence)) | GetSequenceShell(Ok(sequence)) => {
+                (sequence.entries_index() + sequence.permissions_index() + sequence.owners_index())
+                    as usize
+            }
+            GetSequenceRange(Ok(entries)) => entries.len(),
+            GetFull(Ok(FullDataView::Map {
+                entries: MapEntries::Seq(entries),
+                ..
+            })) => entries.len(),
+            GetFull(Ok(FullDataView::Map {
+                entries: MapEntries::Unseq(entries),
+                ..
+            })) => entries.len(),
+            GetFull(Ok(FullDataView::Sequence { entries, .. })) => entries.len(),
+            ListAuthKeysAndVersion(Ok((keys, _))) => keys.len(),
+            _ => 0,
+        };
+        APPROX_FIXED_SIZE + items * APPROX_ENTRY_SIZE
+    }
+
+    /// Returns the HTTP status code best describing the response, e.g. for a REST gateway: 200 on
+    /// success, else the status of the error.
+    pub fn http_status(&self) -> u16 {
+        self.error().map_or(200, Error::http_status)
+    }
+
+    /// Returns `true` if the response carries a result, i.e. the query succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error().is_none()
+    }
+
+    /// Returns `true` if the response carries an error, i.e. the query failed.
+    pub fn is_error(&self) -> bool {
+        self.error().is_some()
+    }
+
+    /// Returns the error carried by the response, whatever its variant, or `None` if it succeeded.
+    pub fn extract_error(&self) -> Option<Error> {
+        self.error().cloned()
+    }
+
+    /// Returns the error contained in the response, if any.
+    fn error(&self) -> Option<&Error> {
+        use QueryResponse::*;
+        match self {
+            GetBlob(res) => res.as_ref().err(),
+            GetMap(res) => res.as_ref().err(),
+            GetMapShell(res) => res.as_ref().err(),
+            GetMapVersion(res) => res.as_ref().err(),
+            ListMapEntries(res) => res.as_ref().err(),
+            ListMapKeys(res) => res.as_ref().err(),
+            ListMapValues(res) => res.as_ref().err(),
+            ListMapUserPermissions(res) => res.as_ref().err(),
+            ListMapPermissions(res) => res.as_ref().err(),
+            GetMapValue(res) => res.as_ref().err(),
+            GetSequence(res) => res.as_ref().err(),
+            GetSequenceShell(res) => res.as_ref().err(),
+            GetSequenceOwner(res) => res.as_ref().err(),
+            GetSequenceRange(res) => res.as_ref().err(),
+            GetSequenceLastEntry(res) => res.as_ref().err(),
+            GetSequencePermissions(res) => res.as_ref().err(),
+            GetSequenceUserPermissions(res) => res.as_ref().err(),
+            GetFull(res) => res.as_ref().err(),
+            GetReplicaKeys(res) => res.as_ref().err(),
+            GetBalance(res) => res.as_ref().err(),
+            GetHistory(res) => res.as_ref().err(),
+            GetHistoryChunk(res) => res.as_ref().err(),
+            GetAccount(res) => res.as_ref().err(),
+            ListAuthKeysAndVersion(res) => res.as_ref().err(),
+        }
+    }
+}
+
 /// The kind of authorisation needed for a request.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum AuthorisationKind {
     /// Authorisation for data requests.
     Data(DataAuthKind),
@@ -507,6 +1173,7 @@ pub enum AuthorisationKind {
 }
 
 /// Authorisation for data requests.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum DataAuthKind {
     /// Read of public data.
     PublicRead,
@@ -517,6 +1184,7 @@ pub enum DataAuthKind {
 }
 
 /// Authorisation for money requests.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum MoneyAuthKind {
     /// Request to get key balance.
     ReadBalance,
@@ -528,6 +1196,7 @@ pub enum MoneyAuthKind {
 
 /// Miscellaneous authorisation kinds.
 /// NB: Not very well categorized yet
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum MiscAuthKind {
     /// Request to manage app keys.
     ManageAppKeys,
@@ -571,21 +1240,34 @@ try_from!(MapValues, ListMapValues);
 try_from!(MapPermissionSet, ListMapUserPermissions);
 try_from!(BTreeMap<PublicKey, MapPermissionSet>, ListMapPermissions);
 try_from!(MapValue, GetMapValue);
-try_from!(Sequence, GetSequence);
+try_from!(Sequence, GetSequence, GetSequenceShell);
 try_from!(SequenceOwner, GetSequenceOwner);
 try_from!(SequenceEntries, GetSequenceRange);
 try_from!((u64, SequenceEntry), GetSequenceLastEntry);
 try_from!(SequencePermissions, GetSequencePermissions);
 try_from!(SequenceUserPermissions, GetSequenceUserPermissions);
+try_from!(FullDataView, GetFull);
 try_from!(Money, GetBalance);
 try_from!(ReplicaPublicKeySet, GetReplicaKeys);
 try_from!(Vec<ReplicaEvent>, GetHistory);
+try_from!(HistoryChunk, GetHistoryChunk);
 try_from!(
     (BTreeMap<PublicKey, AppPermissions>, u64),
     ListAuthKeysAndVersion
 );
 try_from!((Vec<u8>, Signature), GetAccount);
 
+fn log_safe<T: Serialize>(variant: &str, res: &Result<T>) -> String {
+    match res {
+        Ok(value) => format!(
+            "QueryResponse::{}(Ok({} bytes))",
+            variant,
+            utils::serialise(value).len()
+        ),
+        Err(error) => format!("QueryResponse::{}(Err({}))", variant, error),
+    }
+}
+
 impl fmt::Debug for QueryResponse {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use QueryResponse::*;
@@ -615,6 +1297,9 @@ impl fmt::Debug for QueryResponse {
             GetMapValue(res) => write!(f, "QueryResponse::GetMapValue({:?})", ErrorDebug(res)),
             // Sequence
             GetSequence(res) => write!(f, "QueryResponse::GetSequence({:?})", ErrorDebug(res)),
+            GetSequenceShell(res) => {
+                write!(f, "QueryResponse::GetSequenceShell({:?})", ErrorDebug(res))
+            }
             GetSequenceRange(res) => {
                 write!(f, "QueryResponse::GetSequenceRange({:?})", ErrorDebug(res))
             }
@@ -636,12 +1321,17 @@ impl fmt::Debug for QueryResponse {
             GetSequenceOwner(res) => {
                 write!(f, "QueryResponse::GetSequenceOwner({:?})", ErrorDebug(res))
             }
+            // Map and Sequence
+            GetFull(res) => write!(f, "QueryResponse::GetFull({:?})", ErrorDebug(res)),
             // Money
             GetReplicaKeys(res) => {
                 write!(f, "QueryResponse::GetReplicaKeys({:?})", ErrorDebug(res))
             }
             GetBalance(res) => write!(f, "QueryResponse::GetBalance({:?})", ErrorDebug(res)),
             GetHistory(res) => write!(f, "QueryResponse::GetHistory({:?})", ErrorDebug(res)),
+            GetHistoryChunk(res) => {
+                write!(f, "QueryResponse::GetHistoryChunk({:?})", ErrorDebug(res))
+            }
             // Account
             GetAccount(res) => write!(f, "QueryResponse::GetAccount({:?})", ErrorDebug(res)),
             // Client Auth
@@ -657,7 +1347,12 @@ impl fmt::Debug for QueryResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{PublicBlob, UnseqMap};
+    use crate::transfer::test_utils::{debit_agreement_proof, signed_transfer, transfer_validated};
+    use crate::{
+        AppPermissions, BlobAddress, DebitAgreementProof, Keypair, Money, TransferValidated,
+    };
+    use crate::{Ed25519Proof, MapAction, MapAddress, PublicBlob, SequenceAddress, UnseqMap};
+    use signature::Signer;
     use std::convert::{TryFrom, TryInto};
     use unwrap::{unwrap, unwrap_err};
 
@@ -696,7 +1391,901 @@ mod tests {
         assert_eq!(m_data, unwrap!(GetMap(Ok(m_data.clone())).try_into()));
         assert_eq!(
             TryFromError::Response(e.clone()),
-            unwrap_err!(Map::try_from(GetMap(Err(e))))
+            unwrap_err!(Map::try_from(GetMap(Err(e.clone()))))
+        );
+
+        let s_data = Sequence::new_pub(owners, XorName::random(), 1);
+        assert_eq!(
+            s_data,
+            unwrap!(GetSequenceShell(Ok(s_data.clone())).try_into())
+        );
+        assert_eq!(
+            TryFromError::Response(e.clone()),
+            unwrap_err!(Sequence::try_from(GetSequenceShell(Err(e))))
+        );
+    }
+
+    #[test]
+    fn full_data_view() {
+        use QueryResponse::*;
+
+        let mut entries = BTreeMap::new();
+        let _ = entries.insert(vec![1], vec![10]);
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let permissions = MapPermissionSet::new().allow(MapAction::Read);
+        let view = FullDataView::Map {
+            entries: MapEntries::Unseq(entries.clone()),
+            owner,
+            permissions: permissions.clone(),
+        };
+
+        let response = GetFull(Ok(view));
+        match unwrap!(FullDataView::try_from(response)) {
+            FullDataView::Map {
+                entries: MapEntries::Unseq(fetched_entries),
+                owner: fetched_owner,
+                permissions: fetched_permissions,
+            } => {
+                assert_eq!(fetched_entries, entries);
+                assert_eq!(fetched_owner, owner);
+                assert_eq!(fetched_permissions, permissions);
+            }
+            view => panic!("Unexpected view: {:?}", view),
+        }
+
+        assert_eq!(
+            TryFromError::Response(Error::AccessDenied),
+            unwrap_err!(FullDataView::try_from(GetFull(Err(Error::AccessDenied))))
+        );
+        assert_eq!(
+            TryFromError::WrongType,
+            unwrap_err!(FullDataView::try_from(GetSequence(Err(Error::NoSuchData))))
+        );
+    }
+
+    #[test]
+    fn addresses_are_copy() {
+        fn take<T>(value: T) -> T {
+            value
+        }
+
+        let address = Address::Section(XorName::random());
+        let moved = take(address);
+        assert_eq!(address, moved);
+        assert_eq!(address.xorname(), moved.xorname());
+
+        let map_address = MapAddress::Seq {
+            name: XorName::random(),
+            tag: 43_000,
+        };
+        let moved = take(map_address);
+        assert_eq!(map_address, moved);
+
+        let sequence_address = SequenceAddress::Public {
+            name: XorName::random(),
+            tag: 43_000,
+        };
+        let moved = take(sequence_address);
+        assert_eq!(sequence_address, moved);
+    }
+
+    fn client_envelope(message: Message) -> MsgEnvelope {
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::thread_rng());
+        let signature = keypair.sign(&utils::serialise(&message));
+        MsgEnvelope {
+            message,
+            origin: MsgSender::Client(Proof::Ed25519(Ed25519Proof {
+                public_key: keypair.public,
+                signature,
+            })),
+            proxies: vec![],
+        }
+    }
+
+    #[test]
+    fn destinations() {
+        let signed_transfer = gen_transfer_validated().signed_transfer;
+        let from = signed_transfer.from();
+        let envelope = client_envelope(Message::Cmd {
+            cmd: Cmd::Transfer(TransferCmd::ValidateTransfer(signed_transfer)),
+            id: MessageId::new(),
+        });
+        assert!(envelope.verify());
+        assert_eq!(envelope.destinations(), vec![Address::Section(from.into())]);
+        assert_eq!(envelope.destinations(), vec![envelope.destination()]);
+
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let envelope = client_envelope(Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id: MessageId::new(),
+        });
+        assert_eq!(envelope.destinations(), vec![Address::Section(key.into())]);
+    }
+
+    #[test]
+    fn address_predicates() {
+        let name = XorName::random();
+        let client = Address::Client(name);
+        let node = Address::Node(name);
+        let section = Address::Section(name);
+
+        assert!(client.is_client() && !client.is_node() && !client.is_section());
+        assert!(!node.is_client() && node.is_node() && !node.is_section());
+        assert!(!section.is_client() && !section.is_node() && section.is_section());
+
+        assert_eq!(client.as_client(), Some(name));
+        assert_eq!(client.as_section(), None);
+        assert_eq!(node.as_node(), Some(name));
+        assert_eq!(node.as_client(), None);
+        assert_eq!(section.as_section(), Some(name));
+        assert_eq!(section.as_node(), None);
+    }
+
+    #[test]
+    fn address_string_round_trip() -> Result<()> {
+        let name = XorName::random();
+        for address in &[
+            Address::Client(name),
+            Address::Node(name),
+            Address::Section(name),
+        ] {
+            let formatted = address.to_string();
+            assert_eq!(formatted.parse::<Address>()?, *address);
+        }
+        assert!(Address::Section(name).to_string().starts_with("section:"));
+
+        let hex_name = hex::encode(name.0);
+        let invalid = vec![
+            format!("vault:{}", hex_name),
+            hex_name.clone(),
+            "node:not hex".to_string(),
+            format!("node:{}", &hex_name[2..]),
+        ];
+        for value in invalid {
+            assert!(match value.parse::<Address>() {
+                Err(Error::FailedToParse(_)) => true,
+                _ => false,
+            });
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reply_address() {
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let query = client_envelope(Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id: MessageId::new(),
+        });
+        let client_address = Address::Client(query.origin.id().into());
+        assert_eq!(
+            query.message.reply_address(&query.origin),
+            Some(client_address)
+        );
+
+        let node_cmd = Message::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::RegisterWallet {
+                wallet: key,
+                section: XorName::random(),
+            }),
+            id: MessageId::new(),
+        };
+        let node = proxy(&query);
+        assert_eq!(
+            node_cmd.reply_address(&node),
+            Some(Address::Node(node.id().into()))
+        );
+
+        let response = Message::QueryResponse {
+            response: QueryResponse::GetBalance(Ok(Money::zero())),
+            id: MessageId::new(),
+            correlation_id: query.id(),
+            query_origin: client_address,
+        };
+        assert_eq!(response.reply_address(&node), None);
+    }
+
+    fn proxy(envelope: &MsgEnvelope) -> MsgSender {
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::thread_rng());
+        let signature = keypair.sign(&utils::serialise(envelope));
+        MsgSender::Node {
+            duty: Duty::Elder(ElderDuties::Gateway),
+            proof: Proof::Ed25519(Ed25519Proof {
+                public_key: keypair.public,
+                signature,
+            }),
+        }
+    }
+
+    #[test]
+    fn envelope_verify_many() {
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let query = |id| Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id,
+        };
+        let valid = client_envelope(query(MessageId::new()));
+        let proxied = client_envelope(query(MessageId::new()));
+        let proxied = proxied.with_proxy(proxy(&proxied));
+        let mut forged = client_envelope(query(MessageId::new()));
+        forged.message = query(MessageId::new());
+        let node = MsgEnvelope::for_node(
+            query(MessageId::new()),
+            Duty::Elder(ElderDuties::Gateway),
+            &NodeKeypairs::new(&mut rand::thread_rng()),
+        );
+
+        let bls = MsgEnvelope::for_client(
+            query(MessageId::new()),
+            &ClientFullId::new_bls(&mut rand::thread_rng()),
+        );
+        let mut forged_bls = bls.clone();
+        forged_bls.message = query(MessageId::new());
+
+        let envelopes = vec![
+            valid.clone(),
+            proxied,
+            forged.clone(),
+            node,
+            bls,
+            forged_bls,
+        ];
+        assert_eq!(
+            MsgEnvelope::verify_many(&envelopes),
+            vec![
+                Ok(()),
+                Ok(()),
+                Err(Error::InvalidSignature),
+                Ok(()),
+                Ok(()),
+                Err(Error::InvalidSignature)
+            ]
+        );
+        for (envelope, result) in envelopes.iter().zip(MsgEnvelope::verify_many(&envelopes)) {
+            assert_eq!(envelope.verify(), result.is_ok());
+        }
+        assert_eq!(
+            MsgEnvelope::verify_many(&[valid.clone(), valid]),
+            vec![Ok(()), Ok(())]
+        );
+        assert_eq!(
+            MsgEnvelope::verify_many(&[forged]),
+            vec![Err(Error::InvalidSignature)]
+        );
+        assert!(MsgEnvelope::verify_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn envelope_verify_proxy_chain() {
+        let query = |key| Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id: MessageId::new(),
+        };
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let envelope = client_envelope(query(key));
+        let envelope = envelope.with_proxy(proxy(&envelope));
+        let envelope = envelope.with_proxy(proxy(&envelope));
+        assert!(envelope.verify());
+
+        // An earlier proxy replaced, even with one validly signing something else.
+        let mut forged = envelope.clone();
+        forged.proxies[0] = proxy(&client_envelope(query(key)));
+        assert!(!forged.verify());
+
+        // A relay vouching for an origin which did not sign the message.
+        let mut forged = client_envelope(query(key));
+        forged.origin = client_envelope(query(key)).origin;
+        let forged = forged.with_proxy(proxy(&forged));
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn envelope_max_proxies() -> Result<()> {
+        let mut envelope = client_envelope(Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(PublicKey::Bls(
+                threshold_crypto::SecretKey::random().public_key(),
+            ))),
+            id: MessageId::new(),
+        });
+        for _ in 0..MAX_PROXIES {
+            envelope = envelope.try_with_proxy(proxy(&envelope))?;
+        }
+        assert_eq!(envelope.proxy_chain().len(), MAX_PROXIES);
+        assert!(envelope.verify());
+        assert_eq!(
+            envelope.try_with_proxy(proxy(&envelope)),
+            Err(Error::TooManyProxies)
+        );
+
+        // A chain built past the limit anyway is rejected by the recipient.
+        let overlong = envelope.with_proxy(proxy(&envelope));
+        assert!(!overlong.verify());
+        assert_eq!(
+            MsgEnvelope::verify_many(&[envelope, overlong]),
+            vec![Ok(()), Err(Error::TooManyProxies)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sender_verify_against() {
+        let message = Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(PublicKey::Bls(
+                threshold_crypto::SecretKey::random().public_key(),
+            ))),
+            id: MessageId::new(),
+        };
+        let data = utils::serialise(&message);
+        let sender = client_envelope(message).origin;
+        assert!(sender.verify_against(&data).is_ok());
+
+        let forger = ed25519_dalek::Keypair::generate(&mut rand::thread_rng());
+        let forged = MsgSender::Client(Proof::Ed25519(Ed25519Proof {
+            public_key: forger.public,
+            signature: match sender.signature() {
+                Signature::Ed25519(signature) => signature,
+                _ => panic!("Unexpected signature type"),
+            },
+        }));
+        assert_eq!(forged.verify_against(&data), Err(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn http_status() {
+        use QueryResponse::*;
+
+        assert_eq!(GetBalance(Ok(Money::zero())).http_status(), 200);
+        assert_eq!(GetBalance(Err(Error::NoSuchBalance)).http_status(), 404);
+        assert_eq!(GetBlob(Err(Error::AccessDenied)).http_status(), 403);
+        assert_eq!(GetBlob(Err(Error::NoSuchData)).http_status(), 404);
+        assert_eq!(
+            GetHistory(Err(Error::NetworkOther("failure".to_string()))).http_status(),
+            503
+        );
+
+        assert_eq!(CmdError::Data(Error::DataExists).http_status(), 409);
+        assert_eq!(CmdError::Auth(Error::InvalidSignature).http_status(), 401);
+        let insufficient_balance = Error::InsufficientBalance {
+            balance: Money::zero(),
+            required: Money::from_nano(1),
+        };
+        assert_eq!(
+            CmdError::Transfer(TransferError::TransferValidation(insufficient_balance))
+                .http_status(),
+            402
+        );
+    }
+
+    #[test]
+    fn query_response_is_success() {
+        use QueryResponse::*;
+
+        let success = GetBalance(Ok(Money::zero()));
+        assert!(success.is_success());
+        assert!(!success.is_error());
+
+        let failure = GetSequence(Err(Error::NoSuchData));
+        assert!(!failure.is_success());
+        assert!(failure.is_error());
+    }
+
+    #[test]
+    fn query_response_extract_error() {
+        use QueryResponse::*;
+
+        let blob = Blob::Public(PublicBlob::new(vec![1, 2, 3]));
+        assert_eq!(GetBlob(Ok(blob)).extract_error(), None);
+        assert_eq!(
+            GetBlob(Err(Error::NoSuchData)).extract_error(),
+            Some(Error::NoSuchData)
+        );
+        assert_eq!(GetHistory(Ok(vec![])).extract_error(), None);
+        assert_eq!(
+            GetHistory(Err(Error::NoSuchBalance)).extract_error(),
+            Some(Error::NoSuchBalance)
+        );
+        assert_eq!(
+            ListAuthKeysAndVersion(Ok((BTreeMap::new(), 0))).extract_error(),
+            None
+        );
+        assert_eq!(
+            ListAuthKeysAndVersion(Err(Error::AccessDenied)).extract_error(),
+            Some(Error::AccessDenied)
+        );
+    }
+
+    #[test]
+    fn query_response_approx_size() {
+        let blob = Blob::Public(PublicBlob::new(vec![7; 10_000]));
+        let response = QueryResponse::GetBlob(Ok(blob));
+        let exact = utils::serialise(&response).len();
+        let approx = response.approx_size();
+        assert!(approx >= exact / 2 && approx <= exact * 2);
+    }
+
+    #[test]
+    fn event_approx_size() {
+        let client = XorName::random();
+        let event = gen_transfer_validated();
+        let proof = gen_debit_agreement_proof(&event);
+        for event in vec![
+            Event::TransferValidated { client, event },
+            Event::TransferDebitAgreementReached { client, proof },
+        ] {
+            let exact = utils::serialise(&event).len();
+            let approx = event.approx_size();
+            assert!(approx >= exact / 2 && approx <= exact * 2);
+        }
+    }
+
+    #[test]
+    fn query_response_log_safe() {
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let account = vec![0xAB; 32];
+        let signature = keypair.sign(&account);
+        let response = QueryResponse::GetAccount(Ok((account.clone(), signature.clone())));
+
+        let logged = response.log_safe();
+        assert!(logged.starts_with("QueryResponse::GetAccount(Ok("));
+        assert!(!logged.contains(&format!("{:?}", account)));
+        assert!(!logged.contains(&format!("{:?}", signature)));
+        let size = utils::serialise(&(account, signature)).len();
+        assert_eq!(
+            logged,
+            format!("QueryResponse::GetAccount(Ok({} bytes))", size)
+        );
+
+        let response = QueryResponse::GetAccount(Err(Error::NoSuchLoginPacket));
+        assert_eq!(
+            response.log_safe(),
+            format!(
+                "QueryResponse::GetAccount(Err({}))",
+                Error::NoSuchLoginPacket
+            )
+        );
+    }
+
+    #[derive(Default)]
+    struct CountingHandler {
+        queries: usize,
+        query_responses: usize,
+        cmd_errors: usize,
+        node_queries: usize,
+    }
+
+    impl MessageHandler for CountingHandler {
+        fn on_query(&mut self, _query: Query, _id: MessageId) {
+            self.queries += 1;
+        }
+
+        fn on_query_response(
+            &mut self,
+            _response: QueryResponse,
+            _id: MessageId,
+            _correlation_id: MessageId,
+            _query_origin: Address,
+        ) {
+            self.query_responses += 1;
+        }
+
+        fn on_cmd_error(
+            &mut self,
+            _error: CmdError,
+            _id: MessageId,
+            _correlation_id: MessageId,
+            _cmd_origin: Address,
+        ) {
+            self.cmd_errors += 1;
+        }
+
+        fn on_node_query(&mut self, _query: NodeQuery, _id: MessageId) {
+            self.node_queries += 1;
+        }
+    }
+
+    #[test]
+    fn message_dispatch() {
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let origin = Address::Client(XorName::random());
+        let query = || Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id: MessageId::new(),
+        };
+        let messages = vec![
+            query(),
+            query(),
+            Message::QueryResponse {
+                response: QueryResponse::GetBalance(Ok(Money::zero())),
+                id: MessageId::new(),
+                correlation_id: MessageId::new(),
+                query_origin: origin,
+            },
+            Message::CmdError {
+                error: CmdError::Data(Error::NoSuchData),
+                id: MessageId::new(),
+                correlation_id: MessageId::new(),
+                cmd_origin: origin,
+            },
+            // Not handled by `CountingHandler`.
+            Message::NodeCmd {
+                cmd: NodeCmd::System(NodeSystemCmd::RegisterWallet {
+                    wallet: key,
+                    section: XorName::random(),
+                }),
+                id: MessageId::new(),
+            },
+        ];
+
+        let mut handler = CountingHandler::default();
+        for message in messages {
+            message.dispatch(&mut handler);
+        }
+        assert_eq!(handler.queries, 2);
+        assert_eq!(handler.query_responses, 1);
+        assert_eq!(handler.cmd_errors, 1);
+        assert_eq!(handler.node_queries, 0);
+    }
+
+    #[test]
+    fn envelope_priority_queue() {
+        use std::collections::BinaryHeap;
+
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let client_cmd = client_envelope(Message::Cmd {
+            cmd: Cmd::Auth(AuthCmd::DelAuthKey {
+                client: key,
+                key,
+                version: 1,
+            }),
+            id: MessageId::new(),
+        });
+        let node_cmd = client_envelope(Message::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::RegisterWallet {
+                wallet: key,
+                section: XorName::random(),
+            }),
+            id: MessageId::new(),
+        });
+        let cmd_error = client_envelope(Message::CmdError {
+            error: CmdError::Data(Error::NoSuchData),
+            id: MessageId::new(),
+            correlation_id: MessageId::new(),
+            cmd_origin: Address::Client(XorName::random()),
+        });
+        assert_eq!(client_cmd.processing_priority(), Priority::Low);
+        assert_eq!(node_cmd.processing_priority(), Priority::Normal);
+        assert_eq!(cmd_error.processing_priority(), Priority::High);
+
+        let mut queue: BinaryHeap<_> =
+            vec![client_cmd.clone(), cmd_error.clone(), node_cmd.clone()]
+                .into_iter()
+                .map(PrioritizedEnvelope::new)
+                .collect();
+        let popped: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(PrioritizedEnvelope::into_envelope)
+            .collect();
+        assert_eq!(popped, vec![cmd_error, node_cmd, client_cmd]);
+    }
+
+    #[test]
+    fn message_id_from_content() {
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let query = |key| Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(key)),
+            id: MessageId::new(),
+        };
+        let id = MessageId::from_content(&query(key));
+        assert_eq!(id, MessageId::from_content(&query(key)));
+
+        let other_key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        assert_ne!(id, MessageId::from_content(&query(other_key)));
+    }
+
+    #[test]
+    fn envelope_for_client_and_node() {
+        let mut rng = rand::thread_rng();
+        let query = || Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(PublicKey::Bls(
+                threshold_crypto::SecretKey::random().public_key(),
+            ))),
+            id: MessageId::new(),
+        };
+
+        let client = ClientFullId::new_bls(&mut rng);
+        let envelope = MsgEnvelope::for_client(query(), &client);
+        assert!(envelope.verify());
+        assert_eq!(envelope.origin.id(), *client.public_id().public_key());
+
+        let mut node = NodeKeypairs::new(&mut rng);
+        let duty = Duty::Elder(ElderDuties::Gateway);
+        let envelope = MsgEnvelope::for_node(query(), duty, &node);
+        assert!(envelope.verify());
+        assert_eq!(envelope.origin.id(), node.public_key());
+
+        let bls_secret_key = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        node.set_bls_keys(
+            0,
+            bls_secret_key.secret_key_share(0),
+            bls_secret_key.public_keys(),
+        );
+        let envelope = MsgEnvelope::for_node(query(), duty, &node);
+        assert!(envelope.verify());
+        assert_eq!(envelope.origin.id(), node.public_key());
+    }
+
+    #[test]
+    fn envelope_compression() -> Result<()> {
+        let blob = Blob::Public(PublicBlob::new(vec![42; 10_000]));
+        let envelope = client_envelope(Message::QueryResponse {
+            response: QueryResponse::GetBlob(Ok(blob)),
+            id: MessageId::new(),
+            correlation_id: MessageId::new(),
+            query_origin: Address::Client(XorName::random()),
+        });
+        let compressed = envelope.compress()?;
+        assert_eq!(compressed[0], LZ4_COMPRESSED);
+        assert!(compressed.len() < utils::serialise(&envelope).len());
+        assert_eq!(MsgEnvelope::decompress(&compressed)?, envelope);
+
+        let small = client_envelope(Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(PublicKey::Bls(
+                threshold_crypto::SecretKey::random().public_key(),
+            ))),
+            id: MessageId::new(),
+        });
+        let uncompressed = small.compress()?;
+        assert_eq!(uncompressed[0], UNCOMPRESSED);
+        assert_eq!(MsgEnvelope::decompress(&uncompressed)?, small);
+
+        assert!(MsgEnvelope::decompress(&[]).is_err());
+        assert!(MsgEnvelope::decompress(&compressed[..compressed.len() / 2]).is_err());
+        assert!(MsgEnvelope::decompress(&compressed[..4]).is_err());
+
+        // A forged size prefix must not make the recipient allocate that much.
+        let mut forged = vec![LZ4_COMPRESSED];
+        forged.extend(&u32::MAX.to_le_bytes());
+        forged.push(0);
+        assert_eq!(MsgEnvelope::decompress(&forged), Err(Error::ExceededSize));
+        Ok(())
+    }
+
+    #[test]
+    fn envelope_ack() -> Result<()> {
+        let envelope = client_envelope(Message::Query {
+            query: Query::Transfer(TransferQuery::GetBalance(PublicKey::Bls(
+                threshold_crypto::SecretKey::random().public_key(),
+            ))),
+            id: MessageId::new(),
+        });
+        let signer = Keypair::new_ed25519(&mut rand::thread_rng());
+        let ack = envelope.ack(&signer, Duty::Elder(ElderDuties::Gateway))?;
+
+        assert!(ack.verify());
+        assert_eq!(ack.origin.id(), signer.public_key());
+        assert_eq!(ack.destination(), envelope.origin.address());
+        match ack.message {
+            Message::Ack { correlation_id, .. } => assert_eq!(correlation_id, envelope.id()),
+            _ => panic!("Unexpected message: {:?}", ack.message),
+        }
+
+        assert_eq!(
+            ack.ack(&signer, Duty::Elder(ElderDuties::Gateway)),
+            Err(Error::InvalidOperation)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cmd_authorisation_kind() {
+        let event = gen_transfer_validated();
+        let payment = gen_debit_agreement_proof(&event);
+        let key = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+
+        let auth = Cmd::Auth(AuthCmd::DelAuthKey {
+            client: key,
+            key,
+            version: 1,
+        });
+        assert_eq!(
+            auth.authorisation_kind(),
+            AuthorisationKind::Misc(MiscAuthKind::ManageAppKeys)
+        );
+
+        let validate = Cmd::Transfer(TransferCmd::ValidateTransfer(event.signed_transfer));
+        assert_eq!(
+            validate.authorisation_kind(),
+            AuthorisationKind::Misc(MiscAuthKind::WriteAndTransfer)
+        );
+        let register = Cmd::Transfer(TransferCmd::RegisterTransfer(payment.clone()));
+        assert_eq!(register.authorisation_kind(), AuthorisationKind::None);
+
+        let data = Cmd::Data {
+            cmd: DataCmd::Blob(BlobWrite::New(Blob::Public(PublicBlob::new(vec![1, 2, 3])))),
+            payment,
+        };
+        assert_eq!(
+            data.authorisation_kind(),
+            AuthorisationKind::Data(DataAuthKind::Write)
+        );
+    }
+
+    #[test]
+    fn query_error_response() {
+        let blob_address = BlobAddress::Public(XorName::random());
+        let sequence_address = SequenceAddress::Public {
+            name: XorName::random(),
+            tag: 43_000,
+        };
+        let key = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+
+        let blob = Query::Data(DataQuery::Blob(BlobRead::Get(blob_address)));
+        assert_eq!(
+            blob.error(Error::NoSuchData),
+            QueryResponse::GetBlob(Err(Error::NoSuchData))
+        );
+        let sequence = Query::Data(DataQuery::Sequence(SequenceRead::Get(sequence_address)));
+        assert_eq!(
+            sequence.error(Error::AccessDenied),
+            QueryResponse::GetSequence(Err(Error::AccessDenied))
+        );
+        let owner = Query::Data(DataQuery::Sequence(SequenceRead::GetOwner(
+            sequence_address,
+        )));
+        assert_eq!(
+            owner.error(Error::NoSuchData),
+            QueryResponse::GetSequenceOwner(Err(Error::NoSuchData))
+        );
+        let balance = Query::Transfer(TransferQuery::GetBalance(key));
+        assert_eq!(
+            balance.error(Error::NoSuchBalance),
+            QueryResponse::GetBalance(Err(Error::NoSuchBalance))
         );
     }
+
+    #[test]
+    fn query_authorisation_kind() {
+        let key = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+
+        let balance = Query::Transfer(TransferQuery::GetBalance(key));
+        assert_eq!(
+            balance.authorisation_kind(),
+            AuthorisationKind::Money(MoneyAuthKind::ReadBalance)
+        );
+        let history = Query::Transfer(TransferQuery::GetHistory {
+            at: key,
+            since_version: 0,
+        });
+        assert_eq!(
+            history.authorisation_kind(),
+            AuthorisationKind::Money(MoneyAuthKind::ReadHistory)
+        );
+
+        let public_blob = Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Public(
+            XorName::random(),
+        ))));
+        assert_eq!(
+            public_blob.authorisation_kind(),
+            AuthorisationKind::Data(DataAuthKind::PublicRead)
+        );
+        let map = Query::Data(DataQuery::Map(MapRead::Get(MapAddress::Unseq {
+            name: XorName::random(),
+            tag: 15_000,
+        })));
+        assert_eq!(
+            map.authorisation_kind(),
+            AuthorisationKind::Data(DataAuthKind::PrivateRead)
+        );
+    }
+
+    fn gen_transfer_validated() -> TransferValidated {
+        let mut rng = rand::thread_rng();
+        let signed_transfer = unwrap!(signed_transfer(
+            &Keypair::new_ed25519(&mut rng),
+            1,
+            PublicKey::from(&Keypair::new_ed25519(&mut rng)),
+            1_000,
+        ));
+        let replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        transfer_validated(&signed_transfer, &replicas, 0)
+    }
+
+    fn gen_debit_agreement_proof(event: &TransferValidated) -> DebitAgreementProof {
+        let replicas = threshold_crypto::SecretKeySet::random(0, &mut rand::thread_rng());
+        debit_agreement_proof(&event.signed_transfer, &replicas)
+    }
+
+    fn variant_tag<T: Serialize>(value: &T) -> Vec<u8> {
+        utils::serialise(value)[..4].to_vec()
+    }
+
+    // Pins the wire representation of the variants, so that reordering them is caught.
+    #[test]
+    fn event_variant_tags() {
+        let client = XorName::random();
+        let event = gen_transfer_validated();
+        let proof = gen_debit_agreement_proof(&event);
+
+        assert_eq!(
+            variant_tag(&Event::TransferValidated {
+                client,
+                event: event.clone()
+            }),
+            vec![0, 0, 0, 0]
+        );
+        assert_eq!(
+            variant_tag(&Event::TransferDebitAgreementReached { client, proof }),
+            vec![1, 0, 0, 0]
+        );
+
+        let chunk = BlobAddress::Public(XorName::random());
+        let proof =
+            Signature::Bls(threshold_crypto::SecretKey::random().sign(utils::serialise(&chunk)));
+        assert_eq!(
+            variant_tag(&NodeEvent::DuplicationComplete { chunk, proof }),
+            vec![0, 0, 0, 0]
+        );
+        assert_eq!(
+            variant_tag(&NodeEvent::SectionPayoutValidated(event)),
+            vec![1, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn cmd_error_for_cmd() {
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let error = Error::AccessDenied;
+
+        let auth = Cmd::Auth(AuthCmd::InsAuthKey {
+            client: key,
+            key,
+            version: 1,
+            permissions: AppPermissions::default(),
+        });
+        assert_eq!(
+            CmdError::for_cmd(&auth, error.clone()),
+            CmdError::Auth(error.clone())
+        );
+
+        let event = gen_transfer_validated();
+        let payment = gen_debit_agreement_proof(&event);
+        let data = Cmd::Data {
+            cmd: DataCmd::Sequence(SequenceWrite::Delete(SequenceAddress::Private {
+                name: XorName::random(),
+                tag: 43_000,
+            })),
+            payment,
+        };
+        assert_eq!(
+            CmdError::for_cmd(&data, error.clone()),
+            CmdError::Data(error.clone())
+        );
+
+        let transfer = Cmd::Transfer(TransferCmd::ValidateTransfer(event.signed_transfer));
+        assert_eq!(
+            CmdError::for_cmd(&transfer, error.clone()),
+            CmdError::Transfer(TransferError::TransferValidation(error))
+        );
+    }
+
+    #[test]
+    fn same_shape() {
+        use QueryResponse::*;
+
+        let denied = GetBlob(Err(Error::AccessDenied));
+        let missing = GetBlob(Err(Error::NoSuchData));
+        assert!(denied.same_shape(&missing));
+
+        let blob = Blob::Public(PublicBlob::new(vec![1, 3, 1, 4]));
+        assert!(!denied.same_shape(&GetBlob(Ok(blob))));
+        assert!(!denied.same_shape(&GetMap(Err(Error::AccessDenied))));
+    }
+
+    #[test]
+    fn timestamped_message_id() {
+        let earlier = MessageId::new_timestamped(1_590_000_000_000);
+        let later = MessageId::new_timestamped(1_590_000_000_001);
+        assert_eq!(earlier.timestamp(), Some(1_590_000_000_000));
+        assert_eq!(later.timestamp(), Some(1_590_000_000_001));
+        assert!(earlier < later);
+    }
 }
@@ -69,7 +69,10 @@ pub enum NodeDataCmd {
 
 // -------------- Node Events --------------
 
+/// Events only sent internally in the network.
 ///
+/// Variants are serialised by their position, so new variants must only be appended, and
+/// existing ones never reordered or removed.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum NodeEvent {
@@ -295,3 +298,157 @@ impl NodeQuery {
         }
     }
 }
+
+impl NodeCmdError {
+    /// Returns the `Error` wrapped by the error variant, if any.
+    pub fn inner_error(&self) -> Option<&Error> {
+        use NodeCmdError::*;
+        use NodeRewardError::*;
+        match self {
+            Data(NodeDataError::ChunkDuplication { error, .. }) => Some(error),
+            Rewards(RewardClaiming { error, .. })
+            | Rewards(RewardPayoutInitiation { error, .. })
+            | Rewards(RewardPayoutFinalisation { error, .. }) => Some(error),
+            Transfers(NodeTransferError::TransferPropagation(error)) => Some(error),
+        }
+    }
+}
+
+impl NodeRewardQuery {
+    /// Returns a concise description of the query, e.g. for node operator dashboards.
+    pub fn summary(&self) -> String {
+        use NodeRewardQuery::*;
+        match self {
+            GetAccountId {
+                old_node_id,
+                new_node_id,
+            } => format!(
+                "Account id query for node {:?}, relocated to {:?}",
+                old_node_id, new_node_id
+            ),
+        }
+    }
+}
+
+impl NodeRewardError {
+    /// Returns a concise description of the error, including the account and transfer id where
+    /// present, e.g. for node operator dashboards.
+    pub fn summary(&self) -> String {
+        use NodeRewardError::*;
+        match self {
+            RewardClaiming { account_id, error } => {
+                format!("Reward claim for {} failed: {}", account_id, error)
+            }
+            RewardPayoutInitiation { id, account, error } => format!(
+                "Reward payout {}/{} to {} failed to initiate: {}",
+                id.actor, id.counter, account, error
+            ),
+            RewardPayoutFinalisation { id, account, error } => format!(
+                "Reward payout {}/{} to {} failed to finalise: {}",
+                id.actor, id.counter, account, error
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Money;
+    use crdts::Dot;
+    use threshold_crypto::SecretKey;
+
+    fn gen_public_key() -> PublicKey {
+        PublicKey::Bls(SecretKey::random().public_key())
+    }
+
+    fn insufficient_balance() -> Error {
+        Error::InsufficientBalance {
+            balance: Money::zero(),
+            required: Money::from_nano(1),
+        }
+    }
+
+    #[test]
+    fn reward_query_summary() {
+        let old_node_id = XorName::random();
+        let new_node_id = XorName::random();
+        let query = NodeRewardQuery::GetAccountId {
+            old_node_id,
+            new_node_id,
+        };
+        assert_eq!(
+            query.summary(),
+            format!(
+                "Account id query for node {:?}, relocated to {:?}",
+                old_node_id, new_node_id
+            )
+        );
+    }
+
+    #[test]
+    fn reward_claiming_summary() {
+        let account_id = gen_public_key();
+        let error = NodeRewardError::RewardClaiming {
+            account_id,
+            error: Error::NoSuchBalance,
+        };
+        assert_eq!(
+            error.summary(),
+            format!(
+                "Reward claim for {} failed: {}",
+                account_id,
+                Error::NoSuchBalance
+            )
+        );
+    }
+
+    #[test]
+    fn reward_payout_summary() {
+        let section = gen_public_key();
+        let account = gen_public_key();
+        let id = Dot::new(section, 3);
+
+        let initiation = NodeRewardError::RewardPayoutInitiation {
+            id,
+            account,
+            error: insufficient_balance(),
+        };
+        let summary = initiation.summary();
+        assert!(summary.contains(&format!("{}/3", section)));
+        assert!(summary.contains(&account.to_string()));
+        assert!(summary.contains("failed to initiate"));
+        assert!(summary.ends_with(&insufficient_balance().to_string()));
+
+        let finalisation = NodeRewardError::RewardPayoutFinalisation {
+            id,
+            account,
+            error: Error::InvalidSignature,
+        };
+        let summary = finalisation.summary();
+        assert!(summary.contains(&format!("{}/3", section)));
+        assert!(summary.contains(&account.to_string()));
+        assert!(summary.contains("failed to finalise"));
+    }
+
+    #[test]
+    fn node_cmd_error_inner_error() {
+        let data = NodeCmdError::Data(NodeDataError::ChunkDuplication {
+            address: BlobAddress::Public(XorName::random()),
+            error: Error::NoSuchData,
+        });
+        assert_eq!(data.inner_error(), Some(&Error::NoSuchData));
+
+        let reward = NodeCmdError::Rewards(NodeRewardError::RewardPayoutFinalisation {
+            id: Dot::new(gen_public_key(), 1),
+            account: gen_public_key(),
+            error: insufficient_balance(),
+        });
+        assert_eq!(reward.inner_error(), Some(&insufficient_balance()));
+
+        let transfer = NodeCmdError::Transfers(NodeTransferError::TransferPropagation(
+            Error::InvalidSignature,
+        ));
+        assert_eq!(transfer.inner_error(), Some(&Error::InvalidSignature));
+    }
+}
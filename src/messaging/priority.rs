@@ -0,0 +1,79 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::MsgEnvelope;
+use std::cmp::Ordering;
+
+/// Priority with which a node processes a message, the higher the sooner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Cmds and queries from clients.
+    Low,
+    /// Cmds and queries between nodes, keeping the network running.
+    Normal,
+    /// Responses, events, errors and acknowledgements, which complete work already under way.
+    High,
+}
+
+/// Envelope ordered by its processing priority, so that a `BinaryHeap` of them pops the highest
+/// priority envelope first.
+///
+/// Envelopes of the same priority are ordered by message id, so that the order is deterministic.
+#[derive(Clone, Debug)]
+pub struct PrioritizedEnvelope {
+    priority: Priority,
+    envelope: MsgEnvelope,
+}
+
+impl PrioritizedEnvelope {
+    /// Wraps `envelope`, with its `processing_priority`.
+    pub fn new(envelope: MsgEnvelope) -> Self {
+        Self {
+            priority: envelope.processing_priority(),
+            envelope,
+        }
+    }
+
+    /// Returns the priority of the envelope.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Returns the envelope.
+    pub fn envelope(&self) -> &MsgEnvelope {
+        &self.envelope
+    }
+
+    /// Returns the envelope, consuming the wrapper.
+    pub fn into_envelope(self) -> MsgEnvelope {
+        self.envelope
+    }
+}
+
+impl Ord for PrioritizedEnvelope {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.envelope.id().cmp(&self.envelope.id()))
+    }
+}
+
+impl PartialOrd for PrioritizedEnvelope {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PrioritizedEnvelope {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PrioritizedEnvelope {}
@@ -9,9 +9,10 @@
 
 use super::{AuthorisationKind, CmdError, DataAuthKind, QueryResponse};
 use crate::{
-    Error, Sequence, SequenceAddress as Address, SequenceEntry as Entry, SequenceIndex as Index,
-    SequenceOwner as Owner, SequencePrivatePermissions as PrivatePermissions,
-    SequencePublicPermissions as PublicPermissions, SequenceUser as User,
+    Error, Sequence, SequenceAction as Action, SequenceAddress as Address, SequenceEntry as Entry,
+    SequenceIndex as Index, SequenceOwner as Owner,
+    SequencePrivatePermissions as PrivatePermissions,
+    SequencePublicPermissions as PublicPermissions, SequenceTransaction, SequenceUser as User,
     SequenceWriteOp as WriteOp, XorName,
 };
 use serde::{Deserialize, Serialize};
@@ -51,6 +52,8 @@ pub enum SequenceRead {
     },
     /// Get current owner.
     GetOwner(Address),
+    /// Get Sequence entries, current owner and the requester's permissions in one go.
+    GetFull(Address),
 }
 
 /// TODO: docs
@@ -72,6 +75,10 @@ pub enum SequenceWrite {
     SetPublicPermissions(WriteOp<PublicPermissions>),
     /// Set new permissions for private Sequence.
     SetPrivatePermissions(WriteOp<PrivatePermissions>),
+    /// Apply several writes to the Sequence all-or-nothing.
+    ///
+    /// If any of the writes is invalid, none of them is applied.
+    Transaction(SequenceTransaction),
 }
 
 impl SequenceRead {
@@ -86,6 +93,7 @@ impl SequenceRead {
             GetPermissions(_) => QueryResponse::GetSequencePermissions(Err(error)),
             GetUserPermissions { .. } => QueryResponse::GetSequenceUserPermissions(Err(error)),
             GetOwner(_) => QueryResponse::GetSequenceOwner(Err(error)),
+            GetFull(_) => QueryResponse::GetFull(Err(error)),
         }
     }
 
@@ -98,7 +106,8 @@ impl SequenceRead {
             | GetLastEntry(address)
             | GetPermissions(address)
             | GetUserPermissions { address, .. }
-            | GetOwner(address) => {
+            | GetOwner(address)
+            | GetFull(address) => {
                 if address.is_pub() {
                     AuthorisationKind::Data(DataAuthKind::PublicRead)
                 } else {
@@ -108,6 +117,11 @@ impl SequenceRead {
         }
     }
 
+    /// Returns the Sequence action the requester needs to be permitted to perform this read.
+    pub fn action(&self) -> Action {
+        Action::Read
+    }
+
     /// Returns the address of the destination for request.
     pub fn dst_address(&self) -> XorName {
         use SequenceRead::*;
@@ -117,7 +131,8 @@ impl SequenceRead {
             | GetLastEntry(ref address)
             | GetPermissions(ref address)
             | GetUserPermissions { ref address, .. }
-            | GetOwner(ref address) => *address.name(),
+            | GetOwner(ref address)
+            | GetFull(ref address) => *address.name(),
         }
     }
 }
@@ -135,6 +150,7 @@ impl fmt::Debug for SequenceRead {
                 GetPermissions { .. } => "GetSequencePermissions",
                 GetUserPermissions { .. } => "GetUserPermissions",
                 GetOwner { .. } => "GetOwner",
+                GetFull(_) => "GetFullSequence",
             }
         )
     }
@@ -152,6 +168,20 @@ impl SequenceWrite {
         AuthorisationKind::Data(DataAuthKind::Write)
     }
 
+    /// Returns the Sequence action the requester needs to be permitted to perform this write.
+    ///
+    /// Returns `None` for the writes reserved to the current owner, i.e. creating or deleting the
+    /// Sequence and changing its owner, and for transactions, whose writes each need to be
+    /// permitted.
+    pub fn action(&self) -> Option<Action> {
+        use SequenceWrite::*;
+        match self {
+            Edit(_) => Some(Action::Append),
+            SetPublicPermissions(_) | SetPrivatePermissions(_) => Some(Action::ManagePermissions),
+            New(_) | Delete(_) | SetOwner(_) | Transaction(_) => None,
+        }
+    }
+
     /// Returns the address of the destination for request.
     pub fn dst_address(&self) -> XorName {
         use SequenceWrite::*;
@@ -162,6 +192,7 @@ impl SequenceWrite {
             SetPrivatePermissions(ref op) => *op.address.name(),
             SetOwner(ref op) => *op.address.name(),
             Edit(ref op) => *op.address.name(),
+            Transaction(ref transaction) => *transaction.address().name(),
         }
     }
 }
@@ -179,7 +210,50 @@ impl fmt::Debug for SequenceWrite {
                 SetPrivatePermissions(_) => "SetPrivatePermissions",
                 SetOwner(_) => "SetOwner",
                 Edit(_) => "EditSequence",
+                Transaction(_) => "SequenceTransaction",
             }
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PublicKey, Result, SequencePubUserPermissions};
+    use std::collections::BTreeMap;
+    use threshold_crypto::SecretKey;
+
+    #[test]
+    fn request_actions() -> Result<()> {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = Sequence::new_pub(owner, XorName::random(), 43_000);
+        let address = *data.address();
+        let _ = data.set_owner(owner);
+
+        let read = SequenceRead::GetRange {
+            address,
+            range: (Index::FromStart(0), Index::FromEnd(0)),
+        };
+        assert_eq!(read.action(), Action::Read);
+        data.check_permission(read.action(), owner)?;
+
+        let edit = SequenceWrite::Edit(data.append(b"entry".to_vec()));
+        assert_eq!(edit.action(), Some(Action::Append));
+
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(User::Anyone, SequencePubUserPermissions::new(true, false));
+        let set_permissions =
+            SequenceWrite::SetPublicPermissions(data.set_pub_permissions(permissions)?);
+        assert_eq!(set_permissions.action(), Some(Action::ManagePermissions));
+
+        let anyone = PublicKey::Bls(SecretKey::random().public_key());
+        data.check_permission(Action::Append, anyone)?;
+        assert_eq!(
+            data.check_permission(Action::ManagePermissions, anyone),
+            Err(Error::AccessDenied)
+        );
+
+        assert_eq!(SequenceWrite::Delete(address).action(), None);
+        Ok(())
+    }
+}
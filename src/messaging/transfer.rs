@@ -12,9 +12,11 @@
 use super::{
     AuthorisationKind, CmdError, MiscAuthKind, MoneyAuthKind, QueryResponse, TransferError,
 };
-use crate::{DebitAgreementProof, Error, PublicKey, SignedTransfer, Transfer, XorName};
+use crate::{
+    DebitAgreementProof, Error, PublicKey, ReplicaEvent, SignedTransfer, Transfer, XorName,
+};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, fmt};
+use std::{borrow::Cow, collections::BTreeMap, fmt, mem};
 
 /// Money cmd that is sent to network.
 #[allow(clippy::large_enum_variant)]
@@ -46,6 +48,55 @@ pub enum TransferQuery {
     },
 }
 
+/// A part of a transfer history too large to be sent in a single message.
+///
+/// All chunks of a history are sent as responses sharing the same correlation id.
+#[derive(Hash, Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct HistoryChunk {
+    /// The events of this chunk, in history order.
+    pub events: Vec<ReplicaEvent>,
+    /// Position of this chunk in the history, starting at 0.
+    pub seq: u32,
+    /// Whether this is the last chunk of the history.
+    pub is_final: bool,
+}
+
+/// Collects the chunks of a streamed transfer history, which may arrive in any order.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryReassembler {
+    chunks: BTreeMap<u32, Vec<ReplicaEvent>>,
+    final_seq: Option<u32>,
+}
+
+impl HistoryReassembler {
+    /// Constructs an empty reassembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk. Returns the complete history, in order, once the final chunk and all the
+    /// chunks before it have been added, after which the reassembler is empty again.
+    pub fn add(&mut self, chunk: HistoryChunk) -> Option<Vec<ReplicaEvent>> {
+        if chunk.is_final {
+            self.final_seq = Some(chunk.seq);
+        }
+        let _ = self.chunks.insert(chunk.seq, chunk.events);
+
+        let final_seq = self.final_seq?;
+        // Sequence numbers are unique keys, so this holds only when all of 0..=final_seq are in.
+        let complete = self.chunks.len() == final_seq as usize + 1
+            && self.chunks.keys().next_back() == Some(&final_seq);
+        if !complete {
+            return None;
+        }
+
+        let history = self.chunks.values_mut().flat_map(mem::take).collect();
+        self.chunks.clear();
+        self.final_seq = None;
+        Some(history)
+    }
+}
+
 impl TransferCmd {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
@@ -156,3 +207,41 @@ impl fmt::Debug for TransferQuery {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KnownGroupAdded;
+    use threshold_crypto::SecretKeySet;
+
+    fn gen_event() -> ReplicaEvent {
+        let mut rng = rand::thread_rng();
+        ReplicaEvent::KnownGroupAdded(KnownGroupAdded {
+            group: SecretKeySet::random(1, &mut rng).public_keys(),
+        })
+    }
+
+    #[test]
+    fn reassemble_history_chunks() {
+        let events: Vec<_> = (0..3).map(|_| gen_event()).collect();
+        let first = HistoryChunk {
+            events: events[..2].to_vec(),
+            seq: 0,
+            is_final: false,
+        };
+        let last = HistoryChunk {
+            events: events[2..].to_vec(),
+            seq: 1,
+            is_final: true,
+        };
+
+        // Chunks arriving in order.
+        let mut reassembler = HistoryReassembler::new();
+        assert_eq!(reassembler.add(first.clone()), None);
+        assert_eq!(reassembler.add(last.clone()), Some(events.clone()));
+
+        // Chunks arriving out of order.
+        assert_eq!(reassembler.add(last), None);
+        assert_eq!(reassembler.add(first), Some(events));
+    }
+}
@@ -10,6 +10,7 @@
 use crate::errors::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::{
+    convert::TryFrom,
     fmt::{self, Debug, Display, Formatter},
     str::FromStr,
 };
@@ -45,10 +46,25 @@ impl Money {
         self.0.checked_add(rhs.0).map(Self::from_nano)
     }
 
-    /// Computes `self - rhs`, returning `None` if overflow occurred.
+    /// Computes `self - rhs`, returning `None` if overflow occurred, i.e. if `rhs` is more than
+    /// `self`.
     pub fn checked_sub(self, rhs: Money) -> Option<Money> {
         self.0.checked_sub(rhs.0).map(Self::from_nano)
     }
+
+    /// Computes `self * numerator / denominator`, rounding down, e.g. for fees given as a rate.
+    ///
+    /// Returns `Error::InvalidOperation` if `denominator` is zero, and `Error::ExcessiveValue` if
+    /// the result does not fit in `Money`.
+    pub fn fraction(self, numerator: u64, denominator: u64) -> Result<Money> {
+        if denominator == 0 {
+            return Err(Error::InvalidOperation);
+        }
+        let value = u128::from(self.0) * u128::from(numerator) / u128::from(denominator);
+        u64::try_from(value)
+            .map(Self::from_nano)
+            .map_err(|_| Error::ExcessiveValue)
+    }
 }
 
 impl FromStr for Money {
@@ -182,4 +198,39 @@ mod tests {
         assert_eq!(None, Money(0).checked_sub(Money(u64::MAX)));
         assert_eq!(None, Money(10).checked_sub(Money(11)));
     }
+
+    #[test]
+    fn nano_conversion() {
+        assert_eq!(u64::MAX, Money::from_nano(u64::MAX).as_nano());
+        assert_eq!(0, Money::from_nano(0).as_nano());
+        assert_eq!(Money::zero(), Money::from_nano(0));
+        assert_eq!(
+            Some(Money::from_nano(u64::MAX)),
+            Money::from_nano(u64::MAX - 1).checked_add(Money::from_nano(1))
+        );
+    }
+
+    #[test]
+    fn fraction() {
+        // 2.5% of 10 Money.
+        assert_eq!(
+            Ok(Money(250_000_000)),
+            Money(10_000_000_000).fraction(25, 1000)
+        );
+        // Rounds down.
+        assert_eq!(Ok(Money(3)), Money(10).fraction(1, 3));
+        assert_eq!(Ok(Money(0)), Money(1).fraction(1, 2));
+        // The intermediate product would overflow a u64.
+        assert_eq!(
+            Ok(Money(u64::MAX / 10_000 * 9_999)),
+            Money(u64::MAX / 10_000 * 10_000).fraction(9_999, 10_000)
+        );
+        assert_eq!(
+            Ok(Money(u64::MAX)),
+            Money(u64::MAX).fraction(u64::MAX, u64::MAX)
+        );
+
+        assert_eq!(Err(Error::InvalidOperation), Money(10).fraction(1, 0));
+        assert_eq!(Err(Error::ExcessiveValue), Money(u64::MAX).fraction(2, 1));
+    }
 }
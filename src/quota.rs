@@ -0,0 +1,63 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Data, PublicKey};
+use std::collections::BTreeMap;
+
+/// Returns the data owned by `owner` among `data`, e.g. to purge it when its account is closed.
+pub fn filter_owned_by<'a>(data: &'a [Data], owner: &PublicKey) -> Vec<&'a Data> {
+    data.iter()
+        .filter(|data| data.owner_quota_key().as_ref() == Some(owner))
+        .collect()
+}
+
+/// Bytes of stored data attributed to each owner, e.g. for a node enforcing per-owner storage
+/// quotas.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct StorageQuota {
+    usage: BTreeMap<PublicKey, u64>,
+}
+
+impl StorageQuota {
+    /// Constructs an empty `StorageQuota`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attributes the serialised size of `data` to its owner, returning the owner's new usage.
+    ///
+    /// Returns `None`, without tracking anything, if the data has no owner, e.g. a public Blob.
+    pub fn add(&mut self, data: &Data) -> Option<u64> {
+        let owner = data.owner_quota_key()?;
+        let usage = self.usage.entry(owner).or_insert(0);
+        *usage = usage.saturating_add(data.serialised_size());
+        Some(*usage)
+    }
+
+    /// Releases the serialised size of `data` from its owner's usage, returning the owner's new
+    /// usage.
+    ///
+    /// `data` is expected to be as it was when added, as the size of Map and Sequence data grows
+    /// with each mutation.
+    pub fn remove(&mut self, data: &Data) -> Option<u64> {
+        let owner = data.owner_quota_key()?;
+        let usage = self.usage(&owner).saturating_sub(data.serialised_size());
+        if usage == 0 {
+            let _ = self.usage.remove(&owner);
+        } else {
+            let _ = self.usage.insert(owner, usage);
+        }
+        Some(usage)
+    }
+
+    /// Returns the number of bytes attributed to `owner`.
+    pub fn usage(&self, owner: &PublicKey) -> u64 {
+        self.usage.get(owner).copied().unwrap_or(0)
+    }
+}
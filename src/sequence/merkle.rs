@@ -0,0 +1,111 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::Entry;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the hashes of the tree leaves, i.e. of the entries.
+const LEAF_PREFIX: u8 = 0;
+/// Prefix of the hashes of the inner nodes, so that they can't be passed off as leaves.
+const NODE_PREFIX: u8 = 1;
+
+/// Proof that an entry is at a given index of a Sequence with a given Merkle root.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct MerkleProof {
+    /// Index of the entry.
+    pub index: u64,
+    /// Hashes of the siblings on the path from the entry up to the root, each with `true` if it
+    /// is on the left.
+    pub path: Vec<(bool, [u8; 32])>,
+}
+
+impl MerkleProof {
+    /// Returns `true` if `entry` is the entry at `index` of the Sequence with the `root` Merkle
+    /// root.
+    pub fn verify(&self, entry: &[u8], root: &[u8; 32]) -> bool {
+        let hash = self
+            .path
+            .iter()
+            .fold(leaf_hash(entry), |hash, (is_left, sibling)| {
+                if *is_left {
+                    node_hash(sibling, &hash)
+                } else {
+                    node_hash(&hash, sibling)
+                }
+            });
+        hash == *root
+    }
+}
+
+/// Returns the Merkle root of `entries`, or all zeros if there are none.
+pub(super) fn root<'a>(entries: impl Iterator<Item = &'a Entry>) -> [u8; 32] {
+    levels(entries)
+        .last()
+        .and_then(|level| level.first())
+        .copied()
+        .unwrap_or([0; 32])
+}
+
+/// Returns the proof of the entry at `index` in `entries`, if there is such an entry.
+pub(super) fn proof<'a>(
+    entries: impl Iterator<Item = &'a Entry>,
+    index: u64,
+) -> Option<MerkleProof> {
+    let levels = levels(entries);
+    let mut position = index as usize;
+    if position >= levels.first()?.len() {
+        return None;
+    }
+    let mut path = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        // The last node of a level with an odd number of nodes has no sibling and is carried up.
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            path.push((sibling < position, level[sibling]));
+        }
+        position /= 2;
+    }
+    Some(MerkleProof { index, path })
+}
+
+/// Returns the levels of the tree over `entries`, from the leaves up to the root.
+fn levels<'a>(entries: impl Iterator<Item = &'a Entry>) -> Vec<Vec<[u8; 32]>> {
+    let leaves: Vec<_> = entries.map(|entry| leaf_hash(entry)).collect();
+    if leaves.is_empty() {
+        return Vec::new();
+    }
+    let mut levels = vec![leaves];
+    while levels[levels.len() - 1].len() > 1 {
+        let level = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(level);
+    }
+    levels
+}
+
+fn leaf_hash(entry: &[u8]) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity(entry.len() + 1);
+    bytes.push(LEAF_PREFIX);
+    bytes.extend_from_slice(entry);
+    tiny_keccak::sha3_256(&bytes)
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity(65);
+    bytes.push(NODE_PREFIX);
+    bytes.extend_from_slice(left);
+    bytes.extend_from_slice(right);
+    tiny_keccak::sha3_256(&bytes)
+}
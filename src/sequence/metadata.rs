@@ -7,13 +7,13 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::{utils, Error, PublicKey, Result, XorName};
+use crate::{utils, Error, PublicKey, Result, TypeTag, XorName};
 use multibase::Decodable;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Debug, hash::Hash};
 
 /// An action on Sequence data type.
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum Action {
     /// Read from the data.
     Read,
@@ -57,6 +57,12 @@ impl Address {
         }
     }
 
+    /// Derives an `Address` from `owner` and `nonce`, so that it can be recomputed instead of
+    /// stored.
+    pub fn derive(owner: &PublicKey, nonce: &[u8], tag: u64, kind: Kind) -> Self {
+        Self::from_kind(kind, utils::derive_name(owner, nonce), tag)
+    }
+
     /// Returns the kind.
     pub fn kind(&self) -> Kind {
         match self {
@@ -79,6 +85,16 @@ impl Address {
         }
     }
 
+    /// Returns the messaging address of the section responsible for the data.
+    pub fn to_section_address(&self) -> crate::Address {
+        crate::Address::Section(*self.name())
+    }
+
+    /// Returns the name registered for the tag, if any.
+    pub fn type_name(&self) -> Option<&'static str> {
+        TypeTag(self.tag()).name()
+    }
+
     /// Returns true if public.
     pub fn is_pub(&self) -> bool {
         self.kind().is_pub()
@@ -119,6 +135,12 @@ impl Kind {
     pub fn is_private(self) -> bool {
         !self.is_pub()
     }
+
+    /// Returns true if sentried, i.e. if appends can be made conditional on the current entries
+    /// index, which is the case of all Sequences, public or private.
+    pub fn is_sentried(self) -> bool {
+        true
+    }
 }
 
 /// Index of some data.
@@ -182,6 +204,17 @@ pub struct Owner {
     pub permissions_index: u64,
 }
 
+/// Owner proposed by the current owner of a Sequence, which has not accepted the ownership yet.
+///
+/// Proposals are ordered by `owners_index` first, so that the latest one wins when replicas merge.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug)]
+pub struct OwnerProposal {
+    /// The owners index when the proposal was made. The proposal lapses once another owner is set.
+    pub owners_index: u64,
+    /// Proposed owner.
+    pub public_key: PublicKey,
+}
+
 /// Set of public permissions for a user.
 #[derive(Copy, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
 pub struct PubUserPermissions {
@@ -223,6 +256,15 @@ impl PubUserPermissions {
             Action::ManagePermissions => self.manage_permissions,
         }
     }
+
+    /// Layers these permissions on top of `base`: the permissions explicitly set here win, and
+    /// the unset (`None`) ones fall through to `base`.
+    pub fn overlay(self, base: Self) -> Self {
+        Self {
+            append: self.append.or(base.append),
+            manage_permissions: self.manage_permissions.or(base.manage_permissions),
+        }
+    }
 }
 
 /// Set of private permissions for a user.
@@ -234,6 +276,8 @@ pub struct PrivUserPermissions {
     append: bool,
     /// `true` if the user can manage permissions.
     manage_permissions: bool,
+    /// Maximum number of bytes the user can read, if limited.
+    read_limit: Option<u64>,
 }
 
 impl PrivUserPermissions {
@@ -243,10 +287,22 @@ impl PrivUserPermissions {
             read,
             append,
             manage_permissions: manage_perms,
+            read_limit: None,
         }
     }
 
-    /// Sets permissions.
+    /// Limits the number of bytes the user can read to `bytes`.
+    pub fn with_read_limit(mut self, bytes: u64) -> Self {
+        self.read_limit = Some(bytes);
+        self
+    }
+
+    /// Returns the maximum number of bytes the user can read, or `None` if unlimited.
+    pub fn read_limit(self) -> Option<u64> {
+        self.read_limit
+    }
+
+    /// Sets permissions. The read limit is left as it is.
     pub fn set_perms(&mut self, read: bool, append: bool, manage_perms: bool) {
         self.read = read;
         self.append = append;
@@ -284,6 +340,30 @@ pub struct PublicPermissions {
 }
 
 impl PublicPermissions {
+    /// Returns the users whose permission for an action contradicts the one explicitly set for
+    /// `Anyone`, along with that action.
+    ///
+    /// Such entries are valid, as a user's own permissions take precedence, but worth reviewing.
+    pub fn overrides(&self) -> Vec<(PublicKey, Action)> {
+        let mut overrides = Vec::new();
+        for (user, perms) in &self.permissions {
+            let key = match user {
+                User::Key(key) => key,
+                User::Anyone => continue,
+            };
+            for &action in &[Action::Append, Action::ManagePermissions] {
+                let anyone = self.is_action_allowed_by_user(&User::Anyone, action);
+                match (perms.is_allowed(action), anyone) {
+                    (Some(allowed), Some(anyone_allowed)) if allowed != anyone_allowed => {
+                        overrides.push((*key, action))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        overrides
+    }
+
     /// Returns `Some(true)` if `action` is allowed for the provided user and `Some(false)` if it's
     /// not permitted. `None` means that default permissions should be applied.
     fn is_action_allowed_by_user(&self, user: &User, action: Action) -> Option<bool> {
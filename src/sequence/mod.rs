@@ -7,26 +7,35 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+mod merkle;
 mod metadata;
+mod op_buffer;
 mod seq_crdt;
+mod transaction;
 
-use crate::{Error, PublicKey, Result};
+use crate::{utils, BlobAddress, Error, PublicKey, Result};
+pub use merkle::MerkleProof;
 pub use metadata::{
-    Action, Address, Entries, Entry, Index, Indices, Kind, Owner, Perm, Permissions,
+    Action, Address, Entries, Entry, Index, Indices, Kind, Owner, OwnerProposal, Perm, Permissions,
     PrivUserPermissions, PrivatePermissions, PubUserPermissions, PublicPermissions, User,
     UserPermissions,
 };
+pub use op_buffer::OpBuffer;
 use seq_crdt::{Op, SequenceCrdt};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Formatter},
     hash::Hash,
 };
+pub use transaction::{DataWriteOp, Transaction};
 use xor_name::XorName;
 // Type of data used for the 'Actor' in CRDT vector clocks
 type ActorType = PublicKey;
 
+/// Version of the layout of the snapshots produced by `Data::to_snapshot`.
+const SNAPSHOT_VERSION: u8 = 1;
+
 /// Public Sequence.
 pub type PubSeqData = SequenceCrdt<ActorType, PublicPermissions>;
 /// Private Sequence.
@@ -55,6 +64,48 @@ pub struct WriteOp<T> {
     pub crdt_op: Op<T, ActorType>,
 }
 
+/// Owner proposal to apply to the other replicas of a Sequence, as made by `Data::propose_owner`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Hash)]
+pub struct OwnerProposalOp {
+    /// Address of a Sequence object on the network.
+    pub address: Address,
+    /// The proposal to apply.
+    pub proposal: OwnerProposal,
+}
+
+/// Appends of several entries to a Sequence, as made by `Data::append_all`, to be applied on a
+/// replica in one step with `Data::apply_batch_op`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Hash)]
+pub struct BatchWriteOp {
+    /// Address of a Sequence object on the network.
+    pub address: Address,
+    /// The operations appending the entries, in order.
+    pub crdt_ops: Vec<Op<Entry, ActorType>>,
+}
+
+/// Change made to a Sequence, e.g. by applying CRDT operations or merging a replica, as notified
+/// to the clients subscribed to it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DataChange {
+    /// Entries were appended, from index `from` (inclusive) to `to` (exclusive).
+    EntriesAppended {
+        /// Index of the first appended entry.
+        from: u64,
+        /// Index following the last appended entry.
+        to: u64,
+    },
+    /// Permissions were set, at `index` in the permissions history.
+    PermissionsChanged {
+        /// Index of the new permissions.
+        index: u64,
+    },
+    /// An owner was set, at `index` in the owners history.
+    OwnerChanged {
+        /// Index of the new owner.
+        index: u64,
+    },
+}
+
 /// Object storing a Sequence variant.
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
 pub enum Data {
@@ -75,6 +126,38 @@ impl Data {
         Self::Private(PrivSeqData::new(actor, Address::Private { name, tag }))
     }
 
+    /// Serialises the Sequence, with its entries, permissions and owners history, e.g. to persist
+    /// it outside the network.
+    ///
+    /// The snapshot starts with a version byte, so that it can be told apart from snapshots of a
+    /// different layout.
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut bytes = vec![SNAPSHOT_VERSION];
+        bytes.extend(utils::serialise(self));
+        bytes
+    }
+
+    /// Restores a Sequence from a snapshot produced by `to_snapshot`.
+    ///
+    /// Returns `Error::FailedToParse` if the snapshot is of another version, or corrupt.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self> {
+        match bytes.split_first() {
+            Some((&SNAPSHOT_VERSION, data)) => {
+                utils::deserialise(data).map_err(|error| match error {
+                    Error::FailedToParse(reason) => {
+                        Error::FailedToParse(format!("Corrupt Sequence snapshot: {}", reason))
+                    }
+                    error => error,
+                })
+            }
+            Some((version, _)) => Err(Error::FailedToParse(format!(
+                "Unsupported Sequence snapshot version {}, expected {}",
+                version, SNAPSHOT_VERSION
+            ))),
+            None => Err(Error::FailedToParse("Empty Sequence snapshot".to_string())),
+        }
+    }
+
     /// Returns the address.
     pub fn address(&self) -> &Address {
         match self {
@@ -115,14 +198,16 @@ impl Data {
     /// `Err::InvalidOwners` if the last owner is invalid,
     /// `Err::AccessDenied` if the action is not allowed.
     pub fn check_permission(&self, action: Action, requester: PublicKey) -> Result<()> {
+        if self.is_admin(requester) {
+            return Ok(());
+        }
+
         macro_rules! check_perm {
             ($data: ident, $requester: ident, $action: ident) => {
-                $data.check_is_last_owner($requester).or_else(|_| {
-                    $data
-                        .permissions(Index::FromEnd(1))
-                        .ok_or(Error::AccessDenied)?
-                        .is_action_allowed($requester, $action)
-                })
+                $data
+                    .permissions(Index::FromEnd(1))
+                    .ok_or(Error::AccessDenied)?
+                    .is_action_allowed($requester, $action)
             };
         }
 
@@ -137,6 +222,46 @@ impl Data {
         }
     }
 
+    /// Returns how many bytes `requester` can read from the Sequence: `None` if unlimited, e.g. for
+    /// the owner or on a public Sequence, otherwise the read limit of the requester's permissions.
+    ///
+    /// Returns `Error::AccessDenied` if the requester can't read the Sequence at all.
+    pub fn readable_budget(&self, requester: PublicKey) -> Result<Option<u64>> {
+        self.check_permission(Action::Read, requester)?;
+        if self.is_admin(requester) {
+            return Ok(None);
+        }
+        match self {
+            Data::Public(_) => Ok(None),
+            Data::Private(data) => Ok(data
+                .permissions(Index::FromEnd(1))
+                .and_then(|permissions| permissions.permissions.get(&requester))
+                .and_then(|permissions| permissions.read_limit())),
+        }
+    }
+
+    /// Returns the root of the Merkle tree over the entries, in order, so that replicas can cheaply
+    /// tell whether their entries differ. All zeros if there are no entries.
+    pub fn merkle_root(&self) -> [u8; 32] {
+        merkle::root(self.iter())
+    }
+
+    /// Returns the proof that the entry at `index` is part of the Merkle tree over the entries,
+    /// or `None` if there is no such entry.
+    ///
+    /// Comparing the paths of the proofs of two replicas locates where their entries differ.
+    pub fn merkle_proof(&self, index: u64) -> Option<MerkleProof> {
+        merkle::proof(self.iter(), index)
+    }
+
+    /// Returns the highest of the last entries, permissions and owners indices, i.e. a version
+    /// which changes whenever any of them does.
+    pub fn last_modified_version(&self) -> u64 {
+        self.entries_index()
+            .max(self.permissions_index())
+            .max(self.owners_index())
+    }
+
     /// Returns the last entry index.
     pub fn entries_index(&self) -> u64 {
         match self {
@@ -185,6 +310,158 @@ impl Data {
         }
     }
 
+    /// Merges `other`, a replica of the same Sequence, into this one: the entries, permissions
+    /// and owners of `other` which are missing locally are applied.
+    ///
+    /// Returns `Error::InvalidOperation` if `other` has a different address or kind.
+    pub fn merge(&mut self, other: &Data) -> Result<()> {
+        if self.address() != other.address() {
+            return Err(Error::InvalidOperation);
+        }
+        match (self, other) {
+            (Data::Public(data), Data::Public(other)) => data.merge(other),
+            (Data::Private(data), Data::Private(other)) => data.merge(other),
+            _ => return Err(Error::InvalidOperation),
+        }
+        Ok(())
+    }
+
+    /// Merges `other` like `merge`, returning the resulting changes.
+    pub fn merge_with_changes(&mut self, other: &Data) -> Result<Vec<DataChange>> {
+        let indices = self.indices();
+        self.merge(other)?;
+        Ok(self.changes_since(&indices))
+    }
+
+    /// Returns the current entries, owners and permissions indices.
+    pub fn indices(&self) -> Indices {
+        Indices::new(
+            self.entries_index(),
+            self.owners_index(),
+            self.permissions_index(),
+        )
+    }
+
+    /// Returns the changes made since the Sequence was at `indices`, e.g. as returned by
+    /// `indices()` before applying CRDT operations.
+    ///
+    /// Erased entries are not reported.
+    pub fn changes_since(&self, indices: &Indices) -> Vec<DataChange> {
+        let mut changes = Vec::new();
+        if self.entries_index() > indices.entries_index() {
+            changes.push(DataChange::EntriesAppended {
+                from: indices.entries_index(),
+                to: self.entries_index(),
+            });
+        }
+        changes.extend(
+            (indices.permissions_index()..self.permissions_index())
+                .map(|index| DataChange::PermissionsChanged { index }),
+        );
+        changes.extend(
+            (indices.owners_index()..self.owners_index())
+                .map(|index| DataChange::OwnerChanged { index }),
+        );
+        changes
+    }
+
+    /// Returns the operations a replica of this Sequence is missing, given its
+    /// `(entries_index, permissions_index, owners_index)`.
+    ///
+    /// The replica is assumed to hold the same entries, permissions and owners as this one up to
+    /// these indices. A pending owner proposal is always included, as applying it again is
+    /// harmless.
+    pub fn diff(&self, other_indices: (u64, u64, u64)) -> Vec<DataWriteOp> {
+        let address = *self.address();
+        let (entries_index, permissions_index, owners_index) = other_indices;
+        let (entry_ops, permissions_ops, owner_ops) = match self {
+            Data::Public(data) => {
+                let (entry_ops, permissions_ops, owner_ops) =
+                    data.ops_since(entries_index, permissions_index, owners_index);
+                let permissions_ops: Vec<_> = permissions_ops
+                    .into_iter()
+                    .map(|crdt_op| DataWriteOp::SetPublicPermissions(WriteOp { address, crdt_op }))
+                    .collect();
+                (entry_ops, permissions_ops, owner_ops)
+            }
+            Data::Private(data) => {
+                let (entry_ops, permissions_ops, owner_ops) =
+                    data.ops_since(entries_index, permissions_index, owners_index);
+                let permissions_ops: Vec<_> = permissions_ops
+                    .into_iter()
+                    .map(|crdt_op| DataWriteOp::SetPrivatePermissions(WriteOp { address, crdt_op }))
+                    .collect();
+                (entry_ops, permissions_ops, owner_ops)
+            }
+        };
+        entry_ops
+            .into_iter()
+            .map(|crdt_op| DataWriteOp::Edit(WriteOp { address, crdt_op }))
+            .chain(permissions_ops)
+            .chain(
+                owner_ops
+                    .into_iter()
+                    .map(|crdt_op| DataWriteOp::SetOwner(WriteOp { address, crdt_op })),
+            )
+            .chain(
+                self.pending_owner_proposal().map(|proposal| {
+                    DataWriteOp::ProposeOwner(OwnerProposalOp { address, proposal })
+                }),
+            )
+            .collect()
+    }
+
+    /// Returns the latest owner proposal if it is still pending.
+    fn pending_owner_proposal(&self) -> Option<OwnerProposal> {
+        let (proposal, owners_index) = match self {
+            Data::Public(data) => (data.owner_proposal(), data.owners_index()),
+            Data::Private(data) => (data.owner_proposal(), data.owners_index()),
+        };
+        proposal
+            .filter(|proposal| proposal.owners_index == owners_index)
+            .copied()
+    }
+
+    /// Iterates over the entries, in append order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        let iter: Box<dyn Iterator<Item = &Entry>> = match self {
+            Data::Public(data) => Box::new(data.iter()),
+            Data::Private(data) => Box::new(data.iter()),
+        };
+        iter
+    }
+
+    /// Iterates over the entries from `start`, in append order.
+    ///
+    /// Yields nothing if `start` is out of bounds.
+    pub fn iter_from(&self, start: Index) -> impl Iterator<Item = &Entry> {
+        let iter: Box<dyn Iterator<Item = &Entry>> = match self {
+            Data::Public(data) => Box::new(data.iter_from(start)),
+            Data::Private(data) => Box::new(data.iter_from(start)),
+        };
+        iter
+    }
+
+    /// Iterates over the entries from the last one backward, along with their absolute indices.
+    pub fn iter_rev(&self) -> impl Iterator<Item = (u64, &Entry)> + '_ {
+        (0..self.entries_index())
+            .rev()
+            .filter_map(move |index| Some((index, self.get(Index::FromStart(index))?)))
+    }
+
+    /// Returns the distinct Blobs referenced by the entries, as interpreted by `parse`.
+    ///
+    /// Entries `parse` returns `None` for are skipped.
+    pub fn referenced_blobs<F>(&self, parse: F) -> BTreeSet<BlobAddress>
+    where
+        F: Fn(&Entry) -> Option<BlobAddress>,
+    {
+        (0..self.entries_index())
+            .filter_map(|index| self.get(Index::FromStart(index)))
+            .filter_map(parse)
+            .collect()
+    }
+
     /// Fetches owner at index.
     pub fn owner(&self, owners_index: impl Into<Index>) -> Option<&Owner> {
         match self {
@@ -206,7 +483,36 @@ impl Data {
         }
     }
 
+    /// Appends new entry, if the entries index is `expected_index`.
+    ///
+    /// Returns `Error::InvalidSuccessor` with the current entries index otherwise, e.g. if
+    /// another writer appended first.
+    pub fn append_checked(&mut self, entry: Entry, expected_index: u64) -> Result<WriteOp<Entry>> {
+        let entries_index = self.entries_index();
+        if entries_index != expected_index {
+            return Err(Error::InvalidSuccessor(entries_index));
+        }
+        Ok(self.append(entry))
+    }
+
+    /// Appends `entries` in order, e.g. when importing a log, advancing the entries index by
+    /// `entries.len()`.
+    ///
+    /// The appends are returned as a single operation, which other replicas apply in one step
+    /// with `apply_batch_op`.
+    pub fn append_all(&mut self, entries: Vec<Entry>) -> BatchWriteOp {
+        let address = *self.address();
+        let crdt_ops = match self {
+            Data::Public(data) => data.append_all(entries),
+            Data::Private(data) => data.append_all(entries),
+        };
+        BatchWriteOp { address, crdt_ops }
+    }
+
     /// Apply CRDT operation.
+    ///
+    /// Operations are not signed, so their sender must be authenticated before they are applied,
+    /// e.g. with the proof of the `MsgEnvelope` carrying them.
     pub fn apply_crdt_op(&mut self, op: Op<Entry, ActorType>) {
         match self {
             Data::Public(data) => data.apply_crdt_op(op),
@@ -214,6 +520,65 @@ impl Data {
         };
     }
 
+    /// Returns the highest counter of the entries each actor inserted which are held by this
+    /// replica.
+    pub(crate) fn entries_clock(&self) -> BTreeMap<ActorType, u64> {
+        match self {
+            Data::Public(data) => data.entries_clock(),
+            Data::Private(data) => data.entries_clock(),
+        }
+    }
+
+    /// Applies the appends of a batch, as returned by `append_all`, in one step.
+    ///
+    /// Returns `Error::InvalidOperation`, leaving the replica unchanged, if the batch was made on
+    /// another Sequence. Otherwise all the entries of the batch are appended.
+    pub fn apply_batch_op(&mut self, op: BatchWriteOp) -> Result<()> {
+        if op.address != *self.address() {
+            return Err(Error::InvalidOperation);
+        }
+        match self {
+            Data::Public(data) => data.apply_crdt_ops(op.crdt_ops),
+            Data::Private(data) => data.apply_crdt_ops(op.crdt_ops),
+        };
+        Ok(())
+    }
+
+    /// Erases the entries from `start` (inclusive) to `end` (exclusive) of a Private Sequence,
+    /// returning the operations to apply on the other replicas with `apply_crdt_erasure_op`.
+    ///
+    /// The erased entries are gone from the materialised Sequence, so the following entries
+    /// shift down to `start`.
+    /// Returns `Error::InvalidOperation` for a Public Sequence, which can't be erased from, and
+    /// `Error::NoSuchEntry` if the range is out of bounds.
+    pub fn hard_delete_range(&mut self, start: Index, end: Index) -> Result<Vec<WriteOp<Entry>>> {
+        let address = *self.address();
+        match self {
+            Data::Private(data) => {
+                let ops = data.delete_range(start, end).ok_or(Error::NoSuchEntry)?;
+                Ok(ops
+                    .into_iter()
+                    .map(|crdt_op| WriteOp { address, crdt_op })
+                    .collect())
+            }
+            Data::Public(_) => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Apply an erasure CRDT operation, as returned by `hard_delete_range`.
+    ///
+    /// Returns `Error::InvalidOperation` for a Public Sequence, or if the operation is not a
+    /// removal.
+    pub fn apply_crdt_erasure_op(&mut self, op: Op<Entry, ActorType>) -> Result<()> {
+        match (self, &op) {
+            (Data::Private(data), Op::Delete { .. }) => {
+                data.apply_crdt_op(op);
+                Ok(())
+            }
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
     ///   a new permissions entry for Public Sequence.
     pub fn set_pub_permissions(
         &mut self,
@@ -288,6 +653,54 @@ impl Data {
         WriteOp { address, crdt_op }
     }
 
+    /// Proposes `new_owner` as the next owner, replacing any pending proposal, and returns the
+    /// operation to apply to the other replicas with `apply_owner_proposal_op`.
+    ///
+    /// The ownership is only transferred once the proposed owner accepts it with `accept_owner`,
+    /// on any replica the proposal reached; until then the current owner stays in effect.
+    pub fn propose_owner(&mut self, new_owner: PublicKey) -> OwnerProposalOp {
+        let address = *self.address();
+        let proposal = match self {
+            Data::Public(data) => data.propose_owner(new_owner),
+            Data::Private(data) => data.propose_owner(new_owner),
+        };
+        OwnerProposalOp { address, proposal }
+    }
+
+    /// Applies an owner proposal, as returned by `propose_owner`.
+    ///
+    /// Returns `Error::InvalidOperation` if the proposal was made on another Sequence.
+    pub fn apply_owner_proposal_op(&mut self, op: OwnerProposalOp) -> Result<()> {
+        if op.address != *self.address() {
+            return Err(Error::InvalidOperation);
+        }
+        match self {
+            Data::Public(data) => data.apply_owner_proposal(op.proposal),
+            Data::Private(data) => data.apply_owner_proposal(op.proposal),
+        }
+        Ok(())
+    }
+
+    /// Returns the owner proposed with `propose_owner` which has not accepted yet, if any.
+    pub fn pending_owner(&self) -> Option<&PublicKey> {
+        match self {
+            Data::Public(data) => data.pending_owner(),
+            Data::Private(data) => data.pending_owner(),
+        }
+    }
+
+    /// Completes the pending ownership transfer, making `signer` the owner, which also settles
+    /// the proposal.
+    ///
+    /// Returns the operation setting the new owner, or `Error::AccessDenied` if `signer` is not
+    /// the proposed owner.
+    pub fn accept_owner(&mut self, signer: PublicKey) -> Result<WriteOp<Owner>> {
+        if self.pending_owner() != Some(&signer) {
+            return Err(Error::AccessDenied);
+        }
+        Ok(self.set_owner(signer))
+    }
+
     /// Apply Owner CRDT operation.
     pub fn apply_crdt_owner_op(&mut self, op: Op<Owner, ActorType>) {
         match self {
@@ -296,6 +709,28 @@ impl Data {
         };
     }
 
+    /// Returns the owner in effect when the entry at `entry_index` was appended, i.e. the latest
+    /// owner set before the entry.
+    ///
+    /// Returns `None` if there is no such entry, or if no owner was set before it.
+    pub fn owner_at_entry(&self, entry_index: u64) -> Option<&Owner> {
+        if entry_index >= self.entries_index() {
+            return None;
+        }
+        (0..self.owners_index())
+            .rev()
+            .filter_map(|index| self.owner(Index::FromStart(index)))
+            .find(|owner| owner.entries_index <= entry_index)
+    }
+
+    /// Returns `Error::InvalidOwners` if the current owner is a zero key.
+    pub fn validate_owner(&self) -> Result<()> {
+        match self.owner(Index::FromEnd(1)) {
+            Some(owner) if owner.public_key.is_zero() => Err(Error::InvalidOwners),
+            Some(_) | None => Ok(()),
+        }
+    }
+
     /// Checks if the requester is the last owner.
     ///
     /// Returns:
@@ -309,7 +744,16 @@ impl Data {
         }
     }
 
+    /// Returns `true` if `key` is the last owner, in which case all actions are allowed
+    /// regardless of the permissions set.
+    pub fn is_admin(&self, key: PublicKey) -> bool {
+        self.check_is_last_owner(key).is_ok()
+    }
+
     /// Returns user permissions, if applicable.
+    ///
+    /// Only the requested user's entry is looked up and copied, the rest of the permissions at
+    /// `index` are borrowed, so this is the cheap path for answering single-user queries.
     pub fn user_permissions(&self, user: User, index: impl Into<Index>) -> Result<UserPermissions> {
         let user_perm = match self {
             Data::Public(data) => data
@@ -336,6 +780,28 @@ impl Data {
         perms.ok_or(Error::NoSuchEntry)
     }
 
+    /// Returns the public permissions which were in force when the entry at `entry_index` was
+    /// written, along with their index, i.e. the latest permissions set before the entry.
+    ///
+    /// This is for Public Sequences only: returns `Error::InvalidOperation` for a Private one.
+    /// Returns `Error::NoSuchEntry` if there is no such entry, or if no permissions were set
+    /// before it.
+    pub fn permissions_for_entry(&self, entry_index: u64) -> Result<(Index, &PublicPermissions)> {
+        let data = match self {
+            Data::Public(data) => data,
+            Data::Private(_) => return Err(Error::InvalidOperation),
+        };
+        if entry_index >= data.entries_index() {
+            return Err(Error::NoSuchEntry);
+        }
+        (0..data.permissions_index())
+            .rev()
+            .map(Index::FromStart)
+            .filter_map(|index| Some((index, data.permissions(index)?)))
+            .find(|(_, perms)| perms.entries_index <= entry_index)
+            .ok_or(Error::NoSuchEntry)
+    }
+
     /// Returns private permissions, if applicable.
     pub fn private_permissions(&self, index: impl Into<Index>) -> Result<&PrivatePermissions> {
         let perms = match self {
@@ -360,18 +826,56 @@ impl From<PrivSeqData> for Data {
 
 #[cfg(test)]
 mod tests {
+    use crate::{utils, SequenceBatchWriteOp, SequenceDataChange};
     use crate::{
-        Error, PublicKey, Result, Sequence, SequenceAddress, SequenceIndex, SequenceKind,
-        SequencePrivUserPermissions, SequencePubUserPermissions, SequenceUser,
-        SequenceUserPermissions, XorName,
+        BlobAddress, Error, PublicKey, Result, Sequence, SequenceAction, SequenceAddress,
+        SequenceDataWriteOp, SequenceIndex, SequenceKind, SequenceOpBuffer,
+        SequencePrivUserPermissions, SequencePubUserPermissions, SequenceTransaction, SequenceUser,
+        SequenceUserPermissions, SequenceWriteOp, XorName,
     };
     use std::collections::BTreeMap;
     use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
 
     fn gen_public_key() -> PublicKey {
         PublicKey::Bls(SecretKey::random().public_key())
     }
 
+    #[test]
+    fn sequence_data_changes() -> Result<()> {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let _ = replica1.append(b"value0".to_vec());
+        replica2.merge(&replica1)?;
+
+        let _ = replica1.append(b"value1".to_vec());
+        let _ = replica1.append(b"value2".to_vec());
+        let _ = replica1.set_owner(actor);
+        let indices = replica2.indices();
+        let changes = replica2.merge_with_changes(&replica1)?;
+        assert_eq!(
+            changes,
+            vec![
+                SequenceDataChange::EntriesAppended { from: 1, to: 3 },
+                SequenceDataChange::OwnerChanged { index: 0 },
+            ]
+        );
+        assert_eq!(replica2.changes_since(&indices), changes);
+        assert!(replica2.merge_with_changes(&replica1)?.is_empty());
+
+        let indices = replica2.indices();
+        replica2
+            .apply_batch_op(replica1.append_all(vec![b"value3".to_vec(), b"value4".to_vec()]))?;
+        assert_eq!(
+            replica2.changes_since(&indices),
+            vec![SequenceDataChange::EntriesAppended { from: 3, to: 5 }]
+        );
+        Ok(())
+    }
+
     #[test]
     fn sequence_create_public() {
         let actor = gen_public_key();
@@ -406,6 +910,790 @@ mod tests {
         assert_eq!(*sequence.address(), sequence_address);
     }
 
+    #[test]
+    fn sequence_address_kind() {
+        let name = XorName::random();
+        let tag = 43_000;
+
+        let pub_address = SequenceAddress::from_kind(SequenceKind::Public, name, tag);
+        assert!(pub_address.is_pub());
+        assert!(!pub_address.is_private());
+
+        let private_address = SequenceAddress::from_kind(SequenceKind::Private, name, tag);
+        assert!(!private_address.is_pub());
+        assert!(private_address.is_private());
+    }
+
+    #[test]
+    fn sequence_op_buffer_reverse_order() {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+
+        let ops: Vec<_> = (0..3)
+            .map(|i| replica1.append(format!("value{}", i).into_bytes()))
+            .collect();
+
+        let mut buffer = SequenceOpBuffer::new();
+        for op in ops.iter().skip(1).rev() {
+            buffer.push(op.crdt_op.clone());
+            assert_eq!(buffer.apply_all(&mut replica2), 0);
+        }
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(replica2.entries_index(), 0);
+
+        // The first op unblocks the rest.
+        buffer.push(ops[0].crdt_op.clone());
+        assert_eq!(buffer.apply_all(&mut replica2), 3);
+        assert!(buffer.is_empty());
+
+        // Redelivered ops are discarded.
+        buffer.push(ops[1].crdt_op.clone());
+        assert_eq!(buffer.apply_all(&mut replica2), 0);
+        assert!(buffer.is_empty());
+
+        let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        assert_eq!(replica2.entries_index(), 3);
+        assert_eq!(
+            replica1.in_range(all.0, all.1),
+            replica2.in_range(all.0, all.1)
+        );
+    }
+
+    #[test]
+    fn sequence_op_buffer_after_direct_apply() {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+
+        let ops: Vec<_> = (0..3)
+            .map(|i| replica1.append(format!("value{}", i).into_bytes()).crdt_op)
+            .collect();
+
+        // The first two ops reach the replica directly, bypassing the buffer.
+        replica2.apply_crdt_op(ops[0].clone());
+        replica2.apply_crdt_op(ops[1].clone());
+
+        let mut buffer = SequenceOpBuffer::new();
+        // Ops already applied directly are discarded rather than applied again.
+        buffer.push(ops[1].clone());
+        // The next op only depends on ops applied directly, so it is ready.
+        buffer.push(ops[2].clone());
+        assert_eq!(buffer.apply_all(&mut replica2), 1);
+        assert!(buffer.is_empty());
+
+        let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        assert_eq!(replica2.entries_index(), 3);
+        assert_eq!(
+            replica1.in_range(all.0, all.1),
+            replica2.in_range(all.0, all.1)
+        );
+    }
+
+    #[test]
+    fn sequence_referenced_blobs() {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_pub(actor, XorName::random(), 43_000);
+        let chunk = XorName::random();
+        let _ = sequence.append(chunk.0.to_vec());
+        let _ = sequence.append(b"not a chunk".to_vec());
+        let _ = sequence.append(chunk.0.to_vec());
+
+        let parse = |entry: &Vec<u8>| {
+            if entry.len() != chunk.0.len() {
+                return None;
+            }
+            let mut name = [0; 32];
+            name.copy_from_slice(entry);
+            Some(BlobAddress::Public(XorName(name)))
+        };
+        let referenced = sequence.referenced_blobs(parse);
+        assert_eq!(referenced.len(), 1);
+        assert!(referenced.contains(&BlobAddress::Public(chunk)));
+    }
+
+    #[test]
+    fn sequence_zero_owner() -> Result<()> {
+        let zero_key = PublicKey::Ed25519(unwrap!(ed25519_dalek::PublicKey::from_bytes(&[0; 32])));
+        assert!(zero_key.is_zero());
+        assert!(!gen_public_key().is_zero());
+
+        let mut sequence = Sequence::new_private(gen_public_key(), XorName::random(), 43_000);
+        sequence.validate_owner()?;
+        let _ = sequence.set_owner(gen_public_key());
+        sequence.validate_owner()?;
+        let _ = sequence.set_owner(zero_key);
+        assert_eq!(sequence.validate_owner(), Err(Error::InvalidOwners));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_last_modified_version() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_pub(actor, XorName::random(), 43_000);
+        assert_eq!(sequence.last_modified_version(), 0);
+
+        let _ = sequence.set_owner(actor);
+        let _ = sequence.set_pub_permissions(BTreeMap::new())?;
+        let _ = sequence.set_pub_permissions(BTreeMap::new())?;
+        assert_eq!(sequence.last_modified_version(), 2);
+
+        for i in 0..3 {
+            let _ = sequence.append(vec![i]);
+        }
+        let _ = sequence.set_owner(gen_public_key());
+        assert_eq!(sequence.entries_index(), 3);
+        assert_eq!(sequence.permissions_index(), 2);
+        assert_eq!(sequence.owners_index(), 2);
+        assert_eq!(sequence.last_modified_version(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_transaction_is_atomic() -> Result<()> {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let address = *replica1.address();
+
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            SequenceUser::Anyone,
+            SequencePubUserPermissions::new(true, false),
+        );
+        let mut transaction = SequenceTransaction::new(address);
+        transaction.push(SequenceDataWriteOp::SetPublicPermissions(
+            replica1.set_pub_permissions(permissions)?,
+        ));
+        transaction.push(SequenceDataWriteOp::Edit(
+            replica1.append(b"value0".to_vec()),
+        ));
+        transaction.apply(&mut replica2)?;
+        assert_eq!(replica2.entries_index(), 1);
+        assert_eq!(replica2.permissions_index(), 1);
+        assert_eq!(replica2.last_entry(), replica1.last_entry());
+
+        // An op made on another Sequence invalidates the whole transaction.
+        let mut other = Sequence::new_pub(actor, XorName::random(), sequence_tag);
+        let mut transaction = SequenceTransaction::new(address);
+        transaction.push(SequenceDataWriteOp::Edit(
+            replica1.append(b"value1".to_vec()),
+        ));
+        transaction.push(SequenceDataWriteOp::Edit(other.append(b"value2".to_vec())));
+        assert_eq!(
+            transaction.apply(&mut replica2),
+            Err(Error::InvalidOperation)
+        );
+        assert_eq!(replica2.entries_index(), 1);
+
+        // As does permissions of the wrong kind.
+        let mut private = Sequence::new_private(actor, sequence_name, sequence_tag);
+        let mut transaction = SequenceTransaction::new(address);
+        transaction.push(SequenceDataWriteOp::Edit(
+            replica1.append(b"value3".to_vec()),
+        ));
+        transaction.push(SequenceDataWriteOp::SetPrivatePermissions(
+            SequenceWriteOp {
+                address,
+                crdt_op: private.set_private_permissions(BTreeMap::new())?.crdt_op,
+            },
+        ));
+        assert_eq!(
+            transaction.apply(&mut replica2),
+            Err(Error::InvalidOperation)
+        );
+        assert_eq!(replica2.entries_index(), 1);
+        assert_eq!(replica2.permissions_index(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_permissions_for_entry() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_pub(actor, XorName::random(), 43_000);
+        let _ = sequence.append(b"before".to_vec());
+
+        let mut first = BTreeMap::new();
+        let _ = first.insert(
+            SequenceUser::Anyone,
+            SequencePubUserPermissions::new(true, false),
+        );
+        let _ = sequence.set_pub_permissions(first)?;
+        let _ = sequence.append(b"value0".to_vec());
+        let _ = sequence.append(b"value1".to_vec());
+
+        let mut second = BTreeMap::new();
+        let _ = second.insert(
+            SequenceUser::Anyone,
+            SequencePubUserPermissions::new(false, false),
+        );
+        let _ = sequence.set_pub_permissions(second)?;
+        let _ = sequence.append(b"value2".to_vec());
+
+        assert_eq!(sequence.permissions_for_entry(0), Err(Error::NoSuchEntry));
+        for entry_index in 1..3 {
+            let (index, perms) = sequence.permissions_for_entry(entry_index)?;
+            assert_eq!(index, SequenceIndex::FromStart(0));
+            assert_eq!(perms, sequence.pub_permissions(0)?);
+        }
+        let (index, perms) = sequence.permissions_for_entry(3)?;
+        assert_eq!(index, SequenceIndex::FromStart(1));
+        assert_eq!(perms.entries_index, 3);
+        assert_eq!(sequence.permissions_for_entry(4), Err(Error::NoSuchEntry));
+
+        let mut private = Sequence::new_private(actor, XorName::random(), 43_000);
+        let _ = private.append(b"value0".to_vec());
+        assert_eq!(
+            private.permissions_for_entry(0),
+            Err(Error::InvalidOperation)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_address_derive() {
+        let owner = gen_public_key();
+        let nonce = b"app data";
+        let address = SequenceAddress::derive(&owner, nonce, 43_000, SequenceKind::Public);
+        assert_eq!(
+            address,
+            SequenceAddress::derive(&owner, nonce, 43_000, SequenceKind::Public)
+        );
+        assert_eq!(address.kind(), SequenceKind::Public);
+        assert_eq!(address.tag(), 43_000);
+
+        let other = SequenceAddress::derive(&owner, b"app data 2", 43_000, SequenceKind::Public);
+        assert_ne!(address.name(), other.name());
+        let other = SequenceAddress::derive(&gen_public_key(), nonce, 43_000, SequenceKind::Public);
+        assert_ne!(address.name(), other.name());
+    }
+
+    #[test]
+    fn sequence_iter() {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_private(actor, XorName::random(), 43_000);
+        assert_eq!(sequence.iter().next(), None);
+
+        let entries: Vec<_> = (0..4).map(|i| vec![i]).collect();
+        for entry in &entries {
+            let _ = sequence.append(entry.clone());
+        }
+
+        assert!(sequence.iter().eq(entries.iter()));
+        assert_eq!(sequence.iter().filter(|entry| entry[0] % 2 == 0).count(), 2);
+        assert!(sequence
+            .iter_from(SequenceIndex::FromStart(1))
+            .eq(entries[1..].iter()));
+        assert!(sequence
+            .iter_from(SequenceIndex::FromEnd(1))
+            .eq(entries[3..].iter()));
+        assert_eq!(sequence.iter_from(SequenceIndex::FromStart(4)).next(), None);
+        assert_eq!(sequence.iter_from(SequenceIndex::FromStart(5)).next(), None);
+        assert_eq!(sequence.iter_from(SequenceIndex::FromEnd(5)).next(), None);
+    }
+
+    #[test]
+    fn sequence_iter_rev() {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_pub(actor, XorName::random(), 43_000);
+        assert_eq!(sequence.iter_rev().next(), None);
+
+        let entries: Vec<_> = (0..4).map(|i| vec![i]).collect();
+        for entry in &entries {
+            let _ = sequence.append(entry.clone());
+        }
+
+        let reversed: Vec<_> = sequence.iter_rev().collect();
+        let expected: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, entry)| (index as u64, entry))
+            .collect();
+        assert_eq!(reversed, expected);
+    }
+
+    #[test]
+    fn sequence_merge_replicas() -> Result<()> {
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(gen_public_key(), sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(gen_public_key(), sequence_name, sequence_tag);
+
+        let _ = replica1.append(b"value0".to_vec());
+        let _ = replica1.set_pub_permissions(BTreeMap::new())?;
+        let _ = replica2.append(b"value1".to_vec());
+        let _ = replica2.append(b"value2".to_vec());
+        let _ = replica2.set_owner(gen_public_key());
+
+        replica1.merge(&replica2)?;
+        assert_eq!(replica1.entries_index(), 3);
+        assert_eq!(replica1.permissions_index(), 1);
+        assert_eq!(replica1.owners_index(), 1);
+        // Merging again is a no-op.
+        replica1.merge(&replica2)?;
+        assert_eq!(replica1.entries_index(), 3);
+
+        replica2.merge(&replica1)?;
+        assert_eq!(
+            replica2.in_range(SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0)),
+            replica1.in_range(SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0))
+        );
+        assert_eq!(replica2.permissions_index(), 1);
+        assert_eq!(replica2.owner(0), replica1.owner(0));
+
+        let private = Sequence::new_private(gen_public_key(), sequence_name, sequence_tag);
+        assert_eq!(replica1.merge(&private), Err(Error::InvalidOperation));
+        let other = Sequence::new_pub(gen_public_key(), XorName::random(), sequence_tag);
+        assert_eq!(replica1.merge(&other), Err(Error::InvalidOperation));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_hard_delete_range() -> Result<()> {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_private(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_private(actor, sequence_name, sequence_tag);
+        for i in 0..4 {
+            replica2.apply_crdt_op(replica1.append(vec![i]).crdt_op);
+        }
+
+        let ops =
+            replica1.hard_delete_range(SequenceIndex::FromStart(1), SequenceIndex::FromStart(3))?;
+        assert_eq!(ops.len(), 2);
+        assert_eq!(replica1.entries_index(), 2);
+        assert_eq!(replica1.get(SequenceIndex::FromStart(1)), Some(&vec![3]));
+
+        // Applied in a different order, the erasure converges.
+        for op in ops.into_iter().rev() {
+            replica2.apply_crdt_erasure_op(op.crdt_op)?;
+        }
+        let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        assert_eq!(
+            replica2.in_range(all.0, all.1),
+            replica1.in_range(all.0, all.1)
+        );
+
+        assert_eq!(
+            replica1.hard_delete_range(SequenceIndex::FromStart(1), SequenceIndex::FromStart(3)),
+            Err(Error::NoSuchEntry)
+        );
+        let append = replica1.append(b"value".to_vec());
+        assert_eq!(
+            replica2.apply_crdt_erasure_op(append.crdt_op),
+            Err(Error::InvalidOperation)
+        );
+
+        let mut public = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let _ = public.append(b"value".to_vec());
+        assert_eq!(
+            public.hard_delete_range(SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0)),
+            Err(Error::InvalidOperation)
+        );
+        assert_eq!(public.entries_index(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_permission_overrides() -> Result<()> {
+        let owner = gen_public_key();
+        let denied = gen_public_key();
+        let manager = gen_public_key();
+        let unset = gen_public_key();
+        let mut sequence = Sequence::new_pub(owner, XorName::random(), 43_000);
+
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            SequenceUser::Anyone,
+            SequencePubUserPermissions::new(true, false),
+        );
+        let _ = permissions.insert(
+            SequenceUser::Key(denied),
+            SequencePubUserPermissions::new(false, None),
+        );
+        let _ = permissions.insert(
+            SequenceUser::Key(manager),
+            SequencePubUserPermissions::new(true, true),
+        );
+        let _ = permissions.insert(
+            SequenceUser::Key(unset),
+            SequencePubUserPermissions::new(None, None),
+        );
+        let _ = sequence.set_pub_permissions(permissions)?;
+
+        let overrides = sequence.pub_permissions(0)?.overrides();
+        assert_eq!(overrides.len(), 2);
+        assert!(overrides.contains(&(denied, SequenceAction::Append)));
+        assert!(overrides.contains(&(manager, SequenceAction::ManagePermissions)));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_diff() -> Result<()> {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        replica2.apply_crdt_op(replica1.append(b"value0".to_vec()).crdt_op);
+
+        let _ = replica1.append(b"value1".to_vec());
+        let _ = replica1.set_pub_permissions(BTreeMap::new())?;
+        let _ = replica1.append(b"value2".to_vec());
+        let _ = replica1.set_owner(actor);
+
+        let indices = (
+            replica2.entries_index(),
+            replica2.permissions_index(),
+            replica2.owners_index(),
+        );
+        assert_eq!(indices, (1, 0, 0));
+        let ops = replica1.diff(indices);
+        assert_eq!(ops.len(), 4);
+
+        let mut transaction = SequenceTransaction::new(*replica1.address());
+        for op in ops {
+            transaction.push(op);
+        }
+        transaction.apply(&mut replica2)?;
+        let all = (SequenceIndex::FromStart(0), SequenceIndex::FromEnd(0));
+        assert_eq!(
+            replica2.in_range(all.0, all.1),
+            replica1.in_range(all.0, all.1)
+        );
+        assert_eq!(replica2.pub_permissions(0)?, replica1.pub_permissions(0)?);
+        assert_eq!(replica2.owner(0), replica1.owner(0));
+
+        assert!(replica1
+            .diff((
+                replica1.entries_index(),
+                replica1.permissions_index(),
+                replica1.owners_index()
+            ))
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_append_all_and_apply() -> Result<()> {
+        let actor = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+        let mut replica1 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        let mut replica2 = Sequence::new_pub(actor, sequence_name, sequence_tag);
+        replica2.apply_crdt_op(replica1.append(b"first".to_vec()).crdt_op);
+
+        let entries: Vec<_> = (0..100).map(|i| vec![i]).collect();
+        let batch = replica1.append_all(entries.clone());
+        assert_eq!(batch.crdt_ops.len(), entries.len());
+        assert_eq!(replica1.entries_index(), 1 + entries.len() as u64);
+
+        // The batch travels as a single op, so the replica goes from none to all of its entries.
+        let received: SequenceBatchWriteOp =
+            unwrap!(bincode::deserialize(&utils::serialise(&batch)));
+        assert_eq!(replica2.entries_index(), 1);
+        replica2.apply_batch_op(received)?;
+        assert_eq!(replica2.entries_index(), replica1.entries_index());
+        assert!(replica2.iter().skip(1).eq(entries.iter()));
+        assert!(replica2.iter().eq(replica1.iter()));
+
+        // A batch made on another Sequence is rejected as a whole.
+        let mut other = Sequence::new_pub(actor, XorName::random(), sequence_tag);
+        let foreign = other.append_all(vec![b"foreign".to_vec(), b"batch".to_vec()]);
+        assert_eq!(
+            replica2.apply_batch_op(foreign),
+            Err(Error::InvalidOperation)
+        );
+        assert!(replica2.iter().eq(replica1.iter()));
+
+        assert!(replica1.append_all(vec![]).crdt_ops.is_empty());
+        assert_eq!(replica1.entries_index(), 1 + entries.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_append_checked() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_pub(actor, XorName::random(), 43_000);
+
+        let _ = sequence.append_checked(b"value0".to_vec(), 0)?;
+        let _ = sequence.append_checked(b"value1".to_vec(), 1)?;
+        assert_eq!(sequence.entries_index(), 2);
+
+        // Another writer got there first.
+        assert_eq!(
+            sequence.append_checked(b"value2".to_vec(), 1),
+            Err(Error::InvalidSuccessor(2))
+        );
+        assert_eq!(
+            sequence.append_checked(b"value2".to_vec(), 3),
+            Err(Error::InvalidSuccessor(2))
+        );
+        assert_eq!(sequence.entries_index(), 2);
+        assert_eq!(sequence.last_entry(), Some(&b"value1".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_address_to_section_address() {
+        let name = XorName::random();
+        let address = SequenceAddress::Private { name, tag: 43_000 };
+        let section = address.to_section_address();
+        assert_eq!(section, crate::Address::Section(name));
+        assert_eq!(section.xorname(), *address.name());
+    }
+
+    #[test]
+    fn sequence_kind_is_sentried() {
+        assert!(SequenceKind::Public.is_sentried());
+        assert!(SequenceKind::Private.is_sentried());
+    }
+
+    #[test]
+    fn sequence_merkle_root() {
+        let actor = gen_public_key();
+        let mut replica1 = Sequence::new_pub(actor, XorName::random(), 43_000);
+        let mut replica2 = replica1.clone();
+        assert_eq!(replica1.merkle_root(), replica2.merkle_root());
+
+        for i in 0..5u8 {
+            let op = replica1.append(vec![i]);
+            replica2.apply_crdt_op(op.crdt_op);
+        }
+        assert_eq!(replica1.merkle_root(), replica2.merkle_root());
+
+        let _ = replica2.append(b"diverging".to_vec());
+        assert_ne!(replica1.merkle_root(), replica2.merkle_root());
+
+        let root = replica2.merkle_root();
+        for (index, entry) in replica2.iter().enumerate() {
+            let proof = unwrap!(replica2.merkle_proof(index as u64));
+            assert!(proof.verify(entry, &root));
+            assert!(!proof.verify(b"other", &root));
+        }
+        assert!(replica2.merkle_proof(6).is_none());
+
+        // The proofs of the common entries differ only once they are mixed with the new one.
+        let proof1 = unwrap!(replica1.merkle_proof(0));
+        let proof2 = unwrap!(replica2.merkle_proof(0));
+        assert_eq!(proof1.path[..2], proof2.path[..2]);
+        assert_ne!(proof1.path, proof2.path);
+    }
+
+    #[test]
+    fn sequence_readable_budget() -> Result<()> {
+        let owner = gen_public_key();
+        let capped = gen_public_key();
+        let uncapped = gen_public_key();
+        let stranger = gen_public_key();
+        let mut sequence = Sequence::new_private(owner, XorName::random(), 43_000);
+        let _ = sequence.set_owner(owner);
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            capped,
+            SequencePrivUserPermissions::new(true, false, false).with_read_limit(1024),
+        );
+        let _ = permissions.insert(
+            uncapped,
+            SequencePrivUserPermissions::new(true, false, false),
+        );
+        let _ = sequence.set_private_permissions(permissions)?;
+
+        assert_eq!(sequence.readable_budget(owner)?, None);
+        assert_eq!(sequence.readable_budget(capped)?, Some(1024));
+        assert_eq!(sequence.readable_budget(uncapped)?, None);
+        assert_eq!(sequence.readable_budget(stranger), Err(Error::AccessDenied));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_owner_transfer() -> Result<()> {
+        let owner = gen_public_key();
+        let new_owner = gen_public_key();
+        let mut replica1 = Sequence::new_pub(owner, XorName::random(), 43_000);
+        let owner_op = replica1.set_owner(owner);
+        let mut replica2 = Sequence::new_pub(new_owner, *replica1.name(), replica1.tag());
+        replica2.apply_crdt_owner_op(owner_op.crdt_op);
+
+        // The proposal made on one replica reaches the other one.
+        let proposal_op = replica1.propose_owner(new_owner);
+        assert_eq!(replica1.pending_owner(), Some(&new_owner));
+        assert_eq!(replica2.pending_owner(), None);
+        replica2.apply_owner_proposal_op(proposal_op)?;
+        assert_eq!(replica2.pending_owner(), Some(&new_owner));
+        assert_eq!(replica2.owners_index(), 1);
+        replica2.check_is_last_owner(owner)?;
+
+        // It is accepted there, and the acceptance settles it on the first replica.
+        assert_eq!(replica2.accept_owner(owner), Err(Error::AccessDenied));
+        let accept_op = replica2.accept_owner(new_owner)?;
+        assert_eq!(replica2.pending_owner(), None);
+        replica1.apply_crdt_owner_op(accept_op.crdt_op);
+        for replica in &[&replica1, &replica2] {
+            assert_eq!(replica.owners_index(), 2);
+            assert_eq!(
+                unwrap!(replica.owner(SequenceIndex::FromEnd(1))).public_key,
+                new_owner
+            );
+            assert_eq!(replica.pending_owner(), None);
+        }
+        assert_eq!(replica1.accept_owner(new_owner), Err(Error::AccessDenied));
+
+        // Proposals are carried by merges and diffs too.
+        let proposal_op = replica1.propose_owner(owner);
+        let mut merged = replica2.clone();
+        merged.merge(&replica1)?;
+        assert_eq!(merged.pending_owner(), Some(&owner));
+        assert_eq!(
+            replica1.diff((
+                replica2.entries_index(),
+                replica2.permissions_index(),
+                replica2.owners_index()
+            )),
+            vec![SequenceDataWriteOp::ProposeOwner(proposal_op.clone())]
+        );
+
+        let mut other = Sequence::new_pub(owner, XorName::random(), 43_000);
+        assert_eq!(
+            other.apply_owner_proposal_op(proposal_op),
+            Err(Error::InvalidOperation)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_legacy_format() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_private(actor, XorName::random(), 43_000);
+        let _ = sequence.append(b"value".to_vec());
+        let _ = sequence.set_owner(actor);
+
+        // Sequences serialised before owner proposals were added lack the `u32` index of the
+        // current layout, after the `Data` variant index, and the trailing `None` proposal byte.
+        let current = utils::serialise(&sequence);
+        let legacy = [&current[..4], &current[8..current.len() - 1]].concat();
+        let decoded: Sequence = unwrap!(bincode::deserialize(&legacy));
+        assert_eq!(decoded, sequence);
+        assert_eq!(decoded.pending_owner(), None);
+
+        let decoded: Sequence = unwrap!(bincode::deserialize(&current));
+        assert_eq!(decoded, sequence);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_legacy_format_with_permissions() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_private(actor, XorName::random(), 43_000);
+        let _ = sequence.set_owner(actor);
+        let user_permissions = SequencePrivUserPermissions::new(true, true, false);
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(actor, user_permissions);
+        let _ = sequence.set_private_permissions(permissions)?;
+        let _ = sequence.append(b"value".to_vec());
+
+        // Besides the changes covered by `sequence_legacy_format`, private user permissions were
+        // serialised before read limits were added, so without the trailing `None` limit byte.
+        let current = utils::serialise(&sequence);
+        let user_entry = utils::serialise(&(actor, user_permissions));
+        let limit_position = unwrap!(current
+            .windows(user_entry.len())
+            .position(|window| window == &user_entry[..]))
+            + user_entry.len()
+            - 1;
+        let legacy = [
+            &current[..4],
+            &current[8..limit_position],
+            &current[limit_position + 1..current.len() - 1],
+        ]
+        .concat();
+        let decoded: Sequence = unwrap!(bincode::deserialize(&legacy));
+        assert_eq!(decoded, sequence);
+        assert_eq!(
+            decoded.user_permissions(SequenceUser::Key(actor), SequenceIndex::FromEnd(1))?,
+            SequenceUserPermissions::Priv(user_permissions)
+        );
+        assert_eq!(decoded.readable_budget(actor)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_owner_at_entry() {
+        let first_owner = gen_public_key();
+        let second_owner = gen_public_key();
+        let mut sequence = Sequence::new_private(first_owner, XorName::random(), 43_000);
+        let _ = sequence.append(b"unowned".to_vec());
+        let _ = sequence.set_owner(first_owner);
+        let _ = sequence.append(b"value0".to_vec());
+        let _ = sequence.append(b"value1".to_vec());
+        let _ = sequence.set_owner(second_owner);
+        let _ = sequence.append(b"value2".to_vec());
+
+        assert_eq!(sequence.owner_at_entry(0), None);
+        for entry_index in 1..3 {
+            assert_eq!(
+                sequence
+                    .owner_at_entry(entry_index)
+                    .map(|owner| owner.public_key),
+                Some(first_owner)
+            );
+        }
+        assert_eq!(
+            sequence.owner_at_entry(3).map(|owner| owner.public_key),
+            Some(second_owner)
+        );
+        assert_eq!(sequence.owner_at_entry(4), None);
+    }
+
+    #[test]
+    fn sequence_snapshot_round_trip() -> Result<()> {
+        let actor = gen_public_key();
+        let mut sequence = Sequence::new_private(actor, XorName::random(), 43_000);
+        let _ = sequence.set_owner(actor);
+        let _ = sequence.append(b"value0".to_vec());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(actor, SequencePrivUserPermissions::new(true, true, true));
+        let _ = sequence.set_private_permissions(permissions)?;
+        let _ = sequence.append(b"value1".to_vec());
+        let _ = sequence.set_owner(gen_public_key());
+
+        let restored = Sequence::from_snapshot(&sequence.to_snapshot())?;
+        assert_eq!(restored, sequence);
+        assert_eq!(restored.address(), sequence.address());
+        assert!(restored.iter().eq(sequence.iter()));
+        assert_eq!(
+            restored.private_permissions(0)?,
+            sequence.private_permissions(0)?
+        );
+        assert_eq!(restored.owners_index(), 2);
+        assert_eq!(restored.owner(0), sequence.owner(0));
+        assert_eq!(restored.owner(1), sequence.owner(1));
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_snapshot_invalid() {
+        let sequence = Sequence::new_pub(gen_public_key(), XorName::random(), 43_000);
+        let mut snapshot = sequence.to_snapshot();
+
+        let is_parse_error = |bytes: &[u8]| match Sequence::from_snapshot(bytes) {
+            Err(Error::FailedToParse(_)) => true,
+            _ => false,
+        };
+        assert!(is_parse_error(&[]));
+        assert!(is_parse_error(&snapshot[..snapshot.len() - 1]));
+        snapshot[0] += 1;
+        assert!(is_parse_error(&snapshot));
+    }
+
     #[test]
     fn sequence_append_entry_and_apply() {
         let actor = gen_public_key();
@@ -545,6 +1833,84 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sequence_pub_permissions_overlay() {
+        let viewer = SequencePubUserPermissions::new(false, false);
+        let editor = SequencePubUserPermissions::new(true, None);
+
+        let overlaid = editor.overlay(viewer);
+        assert_eq!(overlaid.is_allowed(SequenceAction::Append), Some(true));
+        assert_eq!(
+            overlaid.is_allowed(SequenceAction::ManagePermissions),
+            Some(false)
+        );
+        // Explicit denials in the top layer win too.
+        let restricted = SequencePubUserPermissions::new(None, false);
+        assert_eq!(
+            restricted.overlay(SequencePubUserPermissions::new(true, true)),
+            SequencePubUserPermissions::new(true, false)
+        );
+        // Unset in both layers stays unset, deferring to the permissions for `Anyone`.
+        let unset = SequencePubUserPermissions::new(None, None);
+        assert_eq!(unset.overlay(unset), unset);
+    }
+
+    #[test]
+    fn sequence_user_permissions_match_full_permissions() -> Result<()> {
+        let owner = gen_public_key();
+        let user = gen_public_key();
+        let stranger = gen_public_key();
+        let sequence_name = XorName::random();
+        let sequence_tag = 43_000;
+
+        let mut public = Sequence::new_pub(owner, sequence_name, sequence_tag);
+        let mut pub_perms = BTreeMap::default();
+        let _ = pub_perms.insert(
+            SequenceUser::Anyone,
+            SequencePubUserPermissions::new(true, false),
+        );
+        let _ = pub_perms.insert(
+            SequenceUser::Key(user),
+            SequencePubUserPermissions::new(false, true),
+        );
+        let _ = public.set_pub_permissions(pub_perms)?;
+
+        let index = SequenceIndex::FromStart(0);
+        let full = public.pub_permissions(index)?;
+        for who in &[SequenceUser::Anyone, SequenceUser::Key(user)] {
+            let expected = full
+                .permissions
+                .get(who)
+                .map(|p| SequenceUserPermissions::Public(*p));
+            assert_eq!(expected, Some(public.user_permissions(*who, index)?));
+        }
+        assert_eq!(
+            Err(Error::NoSuchEntry),
+            public.user_permissions(SequenceUser::Key(stranger), index)
+        );
+
+        let mut private = Sequence::new_private(owner, sequence_name, sequence_tag);
+        let mut priv_perms = BTreeMap::default();
+        let _ = priv_perms.insert(user, SequencePrivUserPermissions::new(true, true, false));
+        let _ = private.set_private_permissions(priv_perms)?;
+
+        let full = private.private_permissions(index)?;
+        let expected = full
+            .permissions
+            .get(&user)
+            .map(|p| SequenceUserPermissions::Priv(*p));
+        assert_eq!(
+            expected,
+            Some(private.user_permissions(SequenceUser::Key(user), index)?)
+        );
+        assert_eq!(
+            Err(Error::NoSuchEntry),
+            private.user_permissions(SequenceUser::Anyone, index)
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn sequence_append_owner_and_apply() -> Result<()> {
         let actor = gen_public_key();
@@ -590,4 +1956,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn sequence_owner_is_admin() -> Result<()> {
+        let actor = gen_public_key();
+        let owner = gen_public_key();
+        let other = gen_public_key();
+        let mut sequence = Sequence::new_private(actor, XorName::random(), 43_000);
+        let _ = sequence.set_owner(owner);
+
+        // explicitly deny everything to the owner
+        let mut perms = BTreeMap::default();
+        let _ = perms.insert(owner, SequencePrivUserPermissions::new(false, false, false));
+        let _ = sequence.set_private_permissions(perms)?;
+
+        assert!(sequence.is_admin(owner));
+        assert!(!sequence.is_admin(other));
+
+        for action in &[
+            SequenceAction::Read,
+            SequenceAction::Append,
+            SequenceAction::ManagePermissions,
+        ] {
+            sequence.check_permission(*action, owner)?;
+            assert_eq!(
+                sequence.check_permission(*action, other),
+                Err(Error::AccessDenied)
+            );
+        }
+
+        Ok(())
+    }
 }
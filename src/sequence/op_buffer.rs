@@ -0,0 +1,102 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{seq_crdt::Op, ActorType, Data, Entry};
+use crdts::Dot;
+use std::collections::BTreeMap;
+
+/// Buffer for Sequence entry operations which arrived ahead of the operations they depend on.
+///
+/// An operation is applied once every earlier operation of its author has been applied, and, for
+/// a removal, once the insertion it removes has been applied as well. Operations which were
+/// already applied are discarded.
+///
+/// Besides the operations applied through the buffer, the entries held by the target replica
+/// count as applied, so operations may also reach the replica directly. Removals leave no trace
+/// in the replica though, so they only count once applied through the buffer.
+#[derive(Clone, Debug, Default)]
+pub struct OpBuffer {
+    /// Counter of the last operation known to be applied, per actor.
+    applied: BTreeMap<ActorType, u64>,
+    /// Operations waiting for their dependencies, in arrival order.
+    pending: Vec<Op<Entry, ActorType>>,
+}
+
+impl OpBuffer {
+    /// Constructs an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffers an operation until `apply_all` finds its dependencies satisfied.
+    pub fn push(&mut self, op: Op<Entry, ActorType>) {
+        self.pending.push(op)
+    }
+
+    /// Returns the number of operations still waiting for their dependencies.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if no operations are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Applies to `data` every buffered operation whose dependencies are satisfied, including
+    /// those unblocked by operations applied in the same call.
+    ///
+    /// Returns the number of operations applied.
+    pub fn apply_all(&mut self, data: &mut Data) -> usize {
+        for (actor, counter) in data.entries_clock() {
+            let applied = self.applied.entry(actor).or_insert(0);
+            if counter > *applied {
+                *applied = counter;
+            }
+        }
+
+        let mut count = 0;
+        while let Some(position) = self.pending.iter().position(|op| self.is_ready(op)) {
+            let op = self.pending.remove(position);
+            let (actor, counter) = {
+                let dot = dot(&op);
+                (dot.actor, dot.counter)
+            };
+            if counter <= self.last_applied(&actor) {
+                // Already applied.
+                continue;
+            }
+            let _ = self.applied.insert(actor, counter);
+            data.apply_crdt_op(op);
+            count += 1;
+        }
+        count
+    }
+
+    fn last_applied(&self, actor: &ActorType) -> u64 {
+        self.applied.get(actor).copied().unwrap_or(0)
+    }
+
+    fn is_ready(&self, op: &Op<Entry, ActorType>) -> bool {
+        let dot = dot(op);
+        if dot.counter > self.last_applied(&dot.actor) + 1 {
+            return false;
+        }
+        match op {
+            Op::Insert { .. } => true,
+            Op::Delete { remote, .. } => remote.counter <= self.last_applied(&remote.actor),
+        }
+    }
+}
+
+fn dot(op: &Op<Entry, ActorType>) -> &Dot<ActorType> {
+    match op {
+        Op::Insert { dot, .. } | Op::Delete { dot, .. } => dot,
+    }
+}
@@ -7,15 +7,24 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::metadata::{Address, Entries, Entry, Index, Indices, Owner, Perm};
+use super::metadata::{
+    Address, Entries, Entry, Index, Indices, Owner, OwnerProposal, Perm, PrivUserPermissions,
+    PrivatePermissions, PublicPermissions,
+};
 use crate::{Error, PublicKey, Result};
 use crdts::{lseq::LSeq, CmRDT};
 pub use crdts::{lseq::Op, Actor};
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    ser::SerializeStructVariant,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
     hash::Hash,
 };
+use xor_name::XorName;
 
 /// Since in most of the cases it will be appends operations, having a small
 /// boundary will make the Identifiers' length to be shorter.
@@ -25,7 +34,7 @@ const LSEQ_BOUNDARY: u64 = 1;
 const LSEQ_TREE_BASE: u8 = 10; // arity of 1024 at root
 
 /// Sequence data type as a CRDT
-#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd)]
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd)]
 pub struct SequenceCrdt<A, P>
 where
     A: Actor,
@@ -40,6 +49,168 @@ where
     /// This is the history of owners, with each entry representing an owner. Each single owner
     /// could represent an individual user, or a group of users, depending on the `PublicKey` type.
     owners: LSeq<Owner, A>,
+    /// Latest owner proposal, pending until the proposed owner accepts it or another owner is
+    /// set.
+    owner_proposal: Option<OwnerProposal>,
+}
+
+/// Serialised layout of `SequenceCrdt`.
+///
+/// Sequences serialised before owner proposals were added start with their `Address`, i.e. the
+/// variant index of `Address::Public` or `Address::Private` followed by the name and tag. The two
+/// legacy variants mirror that layout field by field, so those Sequences still deserialise, while
+/// the current layout is told apart by the index of the `Current` variant.
+///
+/// The legacy variants hold the permissions in their legacy layout `L`, see `LegacyPerm`.
+#[derive(Deserialize)]
+#[serde(bound(
+    deserialize = "A: Actor + Deserialize<'de>, P: Deserialize<'de>, L: Deserialize<'de>"
+))]
+enum Format<A: Actor, P, L> {
+    LegacyPublic {
+        name: XorName,
+        tag: u64,
+        data: LSeq<Entry, A>,
+        permissions: LSeq<L, A>,
+        owners: LSeq<Owner, A>,
+    },
+    LegacyPrivate {
+        name: XorName,
+        tag: u64,
+        data: LSeq<Entry, A>,
+        permissions: LSeq<L, A>,
+        owners: LSeq<Owner, A>,
+    },
+    Current {
+        address: Address,
+        data: LSeq<Entry, A>,
+        permissions: LSeq<P, A>,
+        owners: LSeq<Owner, A>,
+        owner_proposal: Option<OwnerProposal>,
+    },
+}
+
+/// Variant index of `Format::Current`.
+const CURRENT_FORMAT_INDEX: u32 = 2;
+
+/// Permissions whose layout in the legacy `Format` variants differs from their current one.
+pub trait LegacyPerm {
+    /// The legacy layout, which serialises in the current layout of `Self`.
+    type Legacy: Serialize + DeserializeOwned;
+}
+
+impl LegacyPerm for PublicPermissions {
+    type Legacy = Self;
+}
+
+impl LegacyPerm for PrivatePermissions {
+    type Legacy = LegacyPrivatePermissions;
+}
+
+/// `PrivatePermissions` as serialised before the user read limits were added.
+#[derive(Serialize, Deserialize)]
+pub struct LegacyPrivatePermissions {
+    permissions: BTreeMap<PublicKey, LegacyPrivUserPermissions>,
+    entries_index: u64,
+    owners_index: u64,
+}
+
+/// `PrivUserPermissions` as serialised before the read limit was added.
+#[derive(Deserialize)]
+struct LegacyPrivUserPermissions {
+    read: bool,
+    append: bool,
+    manage_permissions: bool,
+}
+
+impl Serialize for LegacyPrivUserPermissions {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> std::result::Result<S::Ok, S::Error> {
+        PrivUserPermissions::new(self.read, self.append, self.manage_permissions)
+            .serialize(serialiser)
+    }
+}
+
+/// Converts the permissions history of a legacy `Format` variant to the current layout.
+///
+/// The legacy entries serialise in the current layout, so this re-encodes them rather than
+/// rebuilding the `LSeq` with its identifiers and dots.
+fn upgrade_permissions<A, P>(
+    permissions: LSeq<P::Legacy, A>,
+) -> std::result::Result<LSeq<P, A>, bincode::Error>
+where
+    A: Actor + Serialize + DeserializeOwned,
+    P: LegacyPerm + DeserializeOwned,
+{
+    bincode::deserialize(&bincode::serialize(&permissions)?)
+}
+
+impl<A, P> Serialize for SequenceCrdt<A, P>
+where
+    A: Actor + Serialize,
+    P: Perm + Hash + Clone + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serialiser: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state =
+            serialiser.serialize_struct_variant("Format", CURRENT_FORMAT_INDEX, "Current", 5)?;
+        state.serialize_field("address", &self.address)?;
+        state.serialize_field("data", &self.data)?;
+        state.serialize_field("permissions", &self.permissions)?;
+        state.serialize_field("owners", &self.owners)?;
+        state.serialize_field("owner_proposal", &self.owner_proposal)?;
+        state.end()
+    }
+}
+
+impl<'de, A, P> Deserialize<'de> for SequenceCrdt<A, P>
+where
+    A: Actor + Serialize + DeserializeOwned,
+    P: Perm + LegacyPerm + Hash + Clone + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserialiser: D) -> std::result::Result<Self, D::Error> {
+        let (address, data, permissions, owners, owner_proposal) =
+            match Format::<A, P, P::Legacy>::deserialize(deserialiser)? {
+                Format::LegacyPublic {
+                    name,
+                    tag,
+                    data,
+                    permissions,
+                    owners,
+                } => (
+                    Address::Public { name, tag },
+                    data,
+                    upgrade_permissions(permissions).map_err(D::Error::custom)?,
+                    owners,
+                    None,
+                ),
+                Format::LegacyPrivate {
+                    name,
+                    tag,
+                    data,
+                    permissions,
+                    owners,
+                } => (
+                    Address::Private { name, tag },
+                    data,
+                    upgrade_permissions(permissions).map_err(D::Error::custom)?,
+                    owners,
+                    None,
+                ),
+                Format::Current {
+                    address,
+                    data,
+                    permissions,
+                    owners,
+                    owner_proposal,
+                } => (address, data, permissions, owners, owner_proposal),
+            };
+        Ok(Self {
+            address,
+            data,
+            permissions,
+            owners,
+            owner_proposal,
+        })
+    }
 }
 
 impl<A, P> Display for SequenceCrdt<A, P>
@@ -71,6 +242,7 @@ where
             data: LSeq::new_with_args(actor.clone(), LSEQ_TREE_BASE, LSEQ_BOUNDARY),
             permissions: LSeq::new_with_args(actor.clone(), LSEQ_TREE_BASE, LSEQ_BOUNDARY),
             owners: LSeq::new_with_args(actor, LSEQ_TREE_BASE, LSEQ_BOUNDARY),
+            owner_proposal: None,
         }
     }
 
@@ -100,28 +272,150 @@ where
         self.data.append(entry)
     }
 
+    /// Append new items to the SequenceCrdt, in order.
+    pub fn append_all(&mut self, entries: Vec<Entry>) -> Vec<Op<Entry, A>> {
+        entries
+            .into_iter()
+            .map(|entry| self.data.append(entry))
+            .collect()
+    }
+
     /// Apply CRDT operation.
     pub fn apply_crdt_op(&mut self, op: Op<Entry, A>) {
         self.data.apply(op)
     }
 
+    /// Apply CRDT operations, in order.
+    pub fn apply_crdt_ops(&mut self, ops: Vec<Op<Entry, A>>) {
+        for op in ops {
+            self.data.apply(op)
+        }
+    }
+
+    /// Removes the entries from `start` (inclusive) to `end` (exclusive), returning the
+    /// operations to broadcast to other replicas.
+    ///
+    /// Returns `None` if the range is out of bounds.
+    pub fn delete_range(&mut self, start: Index, end: Index) -> Option<Vec<Op<Entry, A>>> {
+        let start = to_absolute_index(start, self.entries_index() as usize)?;
+        let end = to_absolute_index(end, self.entries_index() as usize)?;
+        if start > end {
+            return None;
+        }
+        // Each removal shifts the following entries down to `start`.
+        (start..end)
+            .map(|_| self.data.delete_index(start))
+            .collect()
+    }
+
     /// Gets the entry at `index` if it exists.
     pub fn get(&self, index: Index) -> Option<&Entry> {
         let i = to_absolute_index(index, self.entries_index() as usize)?;
         self.data.get(i)
     }
 
+    /// Iterates over the entries, in append order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.data.iter()
+    }
+
+    /// Iterates over the entries from `start`, in append order.
+    ///
+    /// Yields nothing if `start` is out of bounds.
+    pub fn iter_from(&self, start: Index) -> impl Iterator<Item = &Entry> {
+        let len = self.data.len();
+        let start = to_absolute_index(start, len).unwrap_or(len);
+        self.data.iter().skip(start)
+    }
+
     /// Gets the last entry.
     pub fn last_entry(&self) -> Option<&Entry> {
         self.data.last()
     }
 
+    /// Returns the highest counter of the entries each actor inserted which are held by this
+    /// replica.
+    pub fn entries_clock(&self) -> BTreeMap<A, u64> {
+        let mut clock = BTreeMap::new();
+        for entry in self.data.raw_entries() {
+            let counter = clock.entry(entry.dot.actor.clone()).or_insert(0);
+            if entry.dot.counter > *counter {
+                *counter = entry.dot.counter;
+            }
+        }
+        clock
+    }
+
+    /// Returns the operations inserting the entries, permissions and owners from the given
+    /// indices onward, i.e. those a replica at these indices is missing.
+    pub fn ops_since(
+        &self,
+        entries_index: u64,
+        permissions_index: u64,
+        owners_index: u64,
+    ) -> (Vec<Op<Entry, A>>, Vec<Op<P, A>>, Vec<Op<Owner, A>>) {
+        (
+            insert_ops_from(&self.data, entries_index),
+            insert_ops_from(&self.permissions, permissions_index),
+            insert_ops_from(&self.owners, owners_index),
+        )
+    }
+
+    /// Applies the entries, permissions, owners and owner proposal of `other` which are missing
+    /// locally.
+    ///
+    /// Removals are not part of the replica state, so entries removed from `other` only are not
+    /// removed locally.
+    pub fn merge(&mut self, other: &Self) {
+        merge_lseq(&mut self.data, &other.data);
+        merge_lseq(&mut self.permissions, &other.permissions);
+        merge_lseq(&mut self.owners, &other.owners);
+        if let Some(proposal) = other.owner_proposal {
+            self.apply_owner_proposal(proposal);
+        }
+    }
+
     /// Gets a complete list of permissions.
     pub fn permissions(&self, index: impl Into<Index>) -> Option<&P> {
         let index = to_absolute_index(index.into(), self.permissions.len())?;
         self.permissions.get(index)
     }
 
+    /// Returns the latest owner proposal, whether it is still pending or not.
+    pub fn owner_proposal(&self) -> Option<&OwnerProposal> {
+        self.owner_proposal.as_ref()
+    }
+
+    /// Returns the proposed owner which has not accepted the ownership yet, if any, i.e. if no
+    /// owner was set since the latest proposal.
+    pub fn pending_owner(&self) -> Option<&PublicKey> {
+        self.owner_proposal
+            .as_ref()
+            .filter(|proposal| proposal.owners_index == self.owners_index())
+            .map(|proposal| &proposal.public_key)
+    }
+
+    /// Proposes `public_key` as the next owner, returning the proposal to apply to other
+    /// replicas.
+    pub fn propose_owner(&mut self, public_key: PublicKey) -> OwnerProposal {
+        let proposal = OwnerProposal {
+            owners_index: self.owners_index(),
+            public_key,
+        };
+        self.owner_proposal = Some(proposal);
+        proposal
+    }
+
+    /// Applies an owner proposal made on another replica, keeping the latest of the proposals.
+    pub fn apply_owner_proposal(&mut self, proposal: OwnerProposal) {
+        if self
+            .owner_proposal
+            .map_or(true, |current| proposal > current)
+        {
+            self.owner_proposal = Some(proposal);
+        }
+    }
+
     /// Returns the owner's public key and the indices at the time it was added.
     pub fn owner(&self, owners_index: impl Into<Index>) -> Option<&Owner> {
         let index = to_absolute_index(owners_index.into(), self.owners.len())?;
@@ -212,6 +506,37 @@ where
 
 // Private helpers
 
+/// Applies to `local` the insertions of the elements of `remote` it doesn't hold yet.
+fn merge_lseq<T: Clone, A: Actor>(local: &mut LSeq<T, A>, remote: &LSeq<T, A>) {
+    let local_ids: BTreeSet<_> = local.raw_entries().iter().map(|entry| &entry.id).collect();
+    let missing: Vec<_> = remote
+        .raw_entries()
+        .iter()
+        .filter(|entry| !local_ids.contains(&entry.id))
+        .map(|entry| Op::Insert {
+            id: entry.id.clone(),
+            dot: entry.dot.clone(),
+            val: entry.val.clone(),
+        })
+        .collect();
+    for op in missing {
+        local.apply(op);
+    }
+}
+
+/// Returns the operations inserting the elements of `lseq` from `index` onward.
+fn insert_ops_from<T: Clone, A: Actor>(lseq: &LSeq<T, A>, index: u64) -> Vec<Op<T, A>> {
+    lseq.raw_entries()
+        .iter()
+        .skip(index as usize)
+        .map(|entry| Op::Insert {
+            id: entry.id.clone(),
+            dot: entry.dot.clone(),
+            val: entry.val.clone(),
+        })
+        .collect()
+}
+
 fn to_absolute_index(index: Index, count: usize) -> Option<usize> {
     match index {
         Index::FromStart(index) if index as usize <= count => Some(index as usize),
@@ -0,0 +1,104 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    Address, Data, Entry, Owner, OwnerProposalOp, PrivatePermissions, PublicPermissions, WriteOp,
+};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Any of the write operations which can be made on the Sequence entries, permissions or owner.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Hash)]
+pub enum DataWriteOp {
+    /// Insert or remove an entry.
+    Edit(WriteOp<Entry>),
+    /// Set a new owner.
+    SetOwner(WriteOp<Owner>),
+    /// Set new permissions for public Sequence.
+    SetPublicPermissions(WriteOp<PublicPermissions>),
+    /// Set new permissions for private Sequence.
+    SetPrivatePermissions(WriteOp<PrivatePermissions>),
+    /// Propose a new owner.
+    ProposeOwner(OwnerProposalOp),
+}
+
+impl DataWriteOp {
+    /// Returns the address of the Sequence the operation is made on.
+    pub fn address(&self) -> &Address {
+        use DataWriteOp::*;
+        match self {
+            Edit(op) => &op.address,
+            SetOwner(op) => &op.address,
+            SetPublicPermissions(op) => &op.address,
+            SetPrivatePermissions(op) => &op.address,
+            ProposeOwner(op) => &op.address,
+        }
+    }
+}
+
+/// Write operations on a single Sequence, to be applied all-or-nothing.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Hash)]
+pub struct Transaction {
+    address: Address,
+    ops: Vec<DataWriteOp>,
+}
+
+impl Transaction {
+    /// Constructs an empty transaction on the Sequence at `address`.
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Adds an operation, to be applied after the ones already added.
+    pub fn push(&mut self, op: DataWriteOp) {
+        self.ops.push(op)
+    }
+
+    /// Returns the address of the Sequence the transaction is made on.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the operations, in the order they are applied.
+    pub fn ops(&self) -> &[DataWriteOp] {
+        &self.ops
+    }
+
+    /// Applies all the operations to `data`, in order.
+    ///
+    /// If any of them is invalid, e.g. made on another Sequence or setting permissions of the
+    /// wrong kind, an error is returned and `data` is left unchanged.
+    pub fn apply(&self, data: &mut Data) -> Result<()> {
+        if *data.address() != self.address {
+            return Err(Error::InvalidOperation);
+        }
+        let mut result = data.clone();
+        for op in &self.ops {
+            if *op.address() != self.address {
+                return Err(Error::InvalidOperation);
+            }
+            match op.clone() {
+                DataWriteOp::Edit(op) => result.apply_crdt_op(op.crdt_op),
+                DataWriteOp::SetOwner(op) => result.apply_crdt_owner_op(op.crdt_op),
+                DataWriteOp::SetPublicPermissions(op) => {
+                    result.apply_crdt_pub_perms_op(op.crdt_op)?
+                }
+                DataWriteOp::SetPrivatePermissions(op) => {
+                    result.apply_crdt_private_perms_op(op.crdt_op)?
+                }
+                DataWriteOp::ProposeOwner(op) => result.apply_owner_proposal_op(op)?,
+            }
+        }
+        *data = result;
+        Ok(())
+    }
+}
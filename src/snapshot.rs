@@ -0,0 +1,29 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{utils, Data, PublicKey, Result, Signature};
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of Map or Sequence data signed by its owner, e.g. so that a backup can be checked
+/// for tampering when it is restored.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SignedSnapshot {
+    /// The data, as it was when the snapshot was taken.
+    pub data: Data,
+    /// The owner's signature over the serialised data.
+    pub signature: Signature,
+}
+
+impl SignedSnapshot {
+    /// Returns `Ok(())` if the snapshot was signed by `owner` and hasn't been modified since, and
+    /// `Err(Error::InvalidSignature)` otherwise.
+    pub fn verify(&self, owner: &PublicKey) -> Result<()> {
+        owner.verify(&self.signature, utils::serialise(&self.data))
+    }
+}
@@ -1,8 +1,11 @@
+use super::errors::{Error, Result};
 use super::keys::{PublicKey, Signature, SignatureShare};
 use super::money::Money;
+use super::utils;
+use bincode::serialized_size;
 use crdts::Dot;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fmt::Debug};
 use threshold_crypto::PublicKeySet;
 
 /// Actor id
@@ -11,6 +14,12 @@ pub type AccountId = PublicKey;
 /// Transfer ID.
 pub type TransferId = Dot<AccountId>;
 
+/// Minimum amount of a transfer, so that histories can't be spammed with free transfers.
+pub const MIN_TRANSFER: Money = Money::from_nano(1);
+
+/// Maximum allowed size for a transfer memo, in bytes.
+pub const MAX_MEMO_SIZE_IN_BYTES: usize = 256;
+
 /// A transfer of money between two keys.
 #[derive(Clone, Hash, Eq, PartialEq, PartialOrd, Serialize, Deserialize, Debug)]
 pub struct Transfer {
@@ -20,9 +29,51 @@ pub struct Transfer {
     pub to: AccountId,
     /// The amount to transfer.
     pub amount: Money,
+    /// Optional reference, e.g. an invoice id. It is part of the signed transfer.
+    pub memo: Option<Vec<u8>>,
 }
 
 impl Transfer {
+    /// Checks that the transfer can be made from an account with the given `balance`, before
+    /// signing it.
+    ///
+    /// Returns `Error::InsufficientBalance` if the amount exceeds the balance.
+    pub fn check_against_balance(&self, balance: Money) -> Result<()> {
+        if self.amount > balance {
+            return Err(Error::InsufficientBalance {
+                balance,
+                required: self.amount,
+            });
+        }
+        Ok(())
+    }
+
+    /// Constructs a new transfer.
+    ///
+    /// Returns `Error::AmountBelowMinimum` if the amount is below `MIN_TRANSFER`, and
+    /// `Error::ExceededSize` if the memo is larger than `MAX_MEMO_SIZE_IN_BYTES`.
+    pub fn new(
+        id: TransferId,
+        to: AccountId,
+        amount: Money,
+        memo: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        if amount < MIN_TRANSFER {
+            return Err(Error::AmountBelowMinimum);
+        }
+        if let Some(ref memo) = memo {
+            if memo.len() > MAX_MEMO_SIZE_IN_BYTES {
+                return Err(Error::ExceededSize);
+            }
+        }
+        Ok(Self {
+            id,
+            to,
+            amount,
+            memo,
+        })
+    }
+
     /// Get the transfer id
     pub fn id(&self) -> TransferId {
         self.id
@@ -42,6 +93,11 @@ impl Transfer {
     pub fn to(&self) -> PublicKey {
         self.to
     }
+
+    /// Get the memo of this transfer
+    pub fn memo(&self) -> Option<&[u8]> {
+        self.memo.as_deref()
+    }
 }
 
 /// The aggregated Replica signatures of the Actor debit cmd.
@@ -76,10 +132,36 @@ impl DebitAgreementProof {
         self.signed_transfer.to()
     }
 
+    /// Get the memo of this transfer
+    pub fn memo(&self) -> Option<&[u8]> {
+        self.signed_transfer.memo()
+    }
+
     /// Get the PublicKeySet of the replica that validated this transfer
     pub fn replica_keys(&self) -> ReplicaPublicKeySet {
         self.replica_key.clone()
     }
+
+    /// Get the transfer id, i.e. the same as `id()`.
+    pub fn transfer_id(&self) -> TransferId {
+        self.id()
+    }
+
+    /// Returns size of this proof after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialized_size(self).unwrap_or(u64::MAX)
+    }
+
+    /// Verifies that the replicas' accumulated signature over the signed transfer is valid for
+    /// `replica_keys`, e.g. before acting on a debit agreement received from the network.
+    ///
+    /// Returns `Error::InvalidSignature` otherwise.
+    pub fn verify(&self, replica_keys: &ReplicaPublicKeySet) -> Result<()> {
+        self.debiting_replicas_sig.verify(
+            &PublicKey::Bls(replica_keys.public_key()),
+            &utils::serialise(&self.signed_transfer),
+        )
+    }
 }
 
 /// An Actor cmd.
@@ -111,6 +193,23 @@ impl SignedTransfer {
     pub fn to(&self) -> PublicKey {
         self.transfer.to
     }
+
+    /// Get the memo of this transfer
+    pub fn memo(&self) -> Option<&[u8]> {
+        self.transfer.memo()
+    }
+
+    /// Verifies the transfer as received by a Replica: the amount must be at least
+    /// `MIN_TRANSFER`, and the actor signature valid for the sender.
+    ///
+    /// Returns `Error::AmountBelowMinimum` or `Error::InvalidSignature` otherwise.
+    pub fn verify(&self) -> Result<()> {
+        if self.amount() < MIN_TRANSFER {
+            return Err(Error::AmountBelowMinimum);
+        }
+        self.actor_signature
+            .verify(&self.from(), &utils::serialise(&self.transfer))
+    }
 }
 
 // ------------------------------------------------------------
@@ -265,3 +364,515 @@ pub struct KnownGroupAdded {
 /// Notification of a Transfer sent to a recipient.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Clone, Serialize, Deserialize, Debug)]
 pub struct TransferNotification(pub DebitAgreementProof);
+
+// ------------------------------------------------------------
+//                      Ledger
+// ------------------------------------------------------------
+
+/// Balances per account, kept by applying transfers in order.
+///
+/// Each transfer is checked against the ledger before it is applied: a transfer id which was
+/// already applied (e.g. a double-spend) or which skips a counter, and a debit exceeding the
+/// sender's balance, are rejected and leave the ledger unchanged.
+/// Signatures are not verified here.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Ledger {
+    balances: BTreeMap<AccountId, Money>,
+    /// Counter of the last transfer applied, per sender.
+    counters: BTreeMap<AccountId, u64>,
+}
+
+impl Ledger {
+    /// Constructs an empty ledger.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens an account with an initial balance.
+    ///
+    /// Returns `Error::BalanceExists` if the account is already known to the ledger.
+    pub fn open(&mut self, id: AccountId, balance: Money) -> Result<()> {
+        if self.balances.contains_key(&id) {
+            return Err(Error::BalanceExists);
+        }
+        let _ = self.balances.insert(id, balance);
+        Ok(())
+    }
+
+    /// Returns the balance of the account, if it is known to the ledger.
+    pub fn balance(&self, id: &AccountId) -> Option<Money> {
+        self.balances.get(id).copied()
+    }
+
+    /// Applies a signed transfer, debiting the sender and crediting the recipient.
+    ///
+    /// The recipient account is opened with a zero balance if it is not yet known.
+    pub fn apply_transfer(&mut self, signed_transfer: &SignedTransfer) -> Result<()> {
+        let from = signed_transfer.from();
+        let to = signed_transfer.to();
+        let amount = signed_transfer.amount();
+
+        let last_counter = self.counters.get(&from).copied().unwrap_or(0);
+        let counter = signed_transfer.id().counter;
+        if counter <= last_counter {
+            return Err(Error::TransferIdExists);
+        } else if counter != last_counter + 1 {
+            return Err(Error::InvalidSuccessor(last_counter));
+        }
+
+        let from_balance = self.balance(&from).ok_or(Error::NoSuchSender)?;
+        let from_balance = from_balance
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientBalance {
+                balance: from_balance,
+                required: amount,
+            })?;
+        // A transfer to self leaves the balance unchanged.
+        let to_balance = if to == from {
+            Some(from_balance)
+        } else {
+            self.balance(&to)
+        };
+        let to_balance = to_balance
+            .unwrap_or_else(Money::zero)
+            .checked_add(amount)
+            .ok_or(Error::ExcessiveValue)?;
+
+        let _ = self.counters.insert(from, counter);
+        let _ = self.balances.insert(from, from_balance);
+        let _ = self.balances.insert(to, to_balance);
+        Ok(())
+    }
+
+    /// Applies the transfer of a debit agreement proof. See `apply_transfer`.
+    pub fn apply_proof(&mut self, proof: &DebitAgreementProof) -> Result<()> {
+        self.apply_transfer(&proof.signed_transfer)
+    }
+}
+
+// ------------------------------------------------------------
+//                      Accumulation
+// ------------------------------------------------------------
+
+/// Accumulates the `TransferValidated` events of the debiting Replicas, until a quorum of their
+/// signature shares can be combined into a `DebitAgreementProof`.
+#[derive(Clone, Debug)]
+pub struct TransferAccumulator {
+    /// The key set of the debiting Replicas, which the shares must be made with.
+    replicas: ReplicaPublicKeySet,
+    /// Pending agreements, by sender and counter of the transfer.
+    pending: BTreeMap<(AccountId, u64), PendingAgreement>,
+}
+
+/// The shares accumulated so far for a transfer, by Replica index.
+#[derive(Clone, Debug)]
+struct PendingAgreement {
+    signed_transfer: SignedTransfer,
+    shares: BTreeMap<usize, threshold_crypto::SignatureShare>,
+}
+
+impl TransferAccumulator {
+    /// Constructs an empty accumulator of the shares of the Replicas with the key set `replicas`.
+    pub fn new(replicas: ReplicaPublicKeySet) -> Self {
+        Self {
+            replicas,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the signature share of a Replica, returning the debit agreement proof once more than
+    /// the threshold of the Replicas have validated the transfer.
+    ///
+    /// A share is ignored if it is not made by one of the expected Replicas or is invalid, if the
+    /// Replica already validated the transfer, or if it was made for another transfer than the
+    /// previous shares with the same id.
+    pub fn add(&mut self, validated: TransferValidated) -> Option<DebitAgreementProof> {
+        let TransferValidated {
+            signed_transfer,
+            replica_signature,
+            replicas,
+        } = validated;
+        if replicas != self.replicas {
+            return None;
+        }
+        let data = utils::serialise(&signed_transfer);
+        if !self
+            .replicas
+            .public_key_share(replica_signature.index)
+            .verify(&replica_signature.share, &data)
+        {
+            return None;
+        }
+
+        let id = (signed_transfer.id().actor, signed_transfer.id().counter);
+        let pending = self.pending.entry(id).or_insert_with(|| PendingAgreement {
+            signed_transfer: signed_transfer.clone(),
+            shares: BTreeMap::new(),
+        });
+        if pending.signed_transfer != signed_transfer
+            || pending.shares.contains_key(&replica_signature.index)
+        {
+            return None;
+        }
+        let _ = pending
+            .shares
+            .insert(replica_signature.index, replica_signature.share);
+        if pending.shares.len() <= self.replicas.threshold() {
+            return None;
+        }
+
+        let pending = self.pending.remove(&id)?;
+        let signature = self
+            .replicas
+            .combine_signatures(pending.shares.iter().map(|(index, share)| (*index, share)))
+            .ok()?;
+        Some(DebitAgreementProof {
+            signed_transfer: pending.signed_transfer,
+            debiting_replicas_sig: Signature::Bls(signature),
+            replica_key: self.replicas.clone(),
+        })
+    }
+
+    /// Returns the number of transfers for which shares are being accumulated.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Transfer fixtures shared by the tests of the modules handling transfers.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use crate::{utils, Keypair};
+    use threshold_crypto::SecretKeySet;
+    use unwrap::unwrap;
+
+    /// Returns the transfer of `nanos` from `from` to `to`, signed by `from`.
+    pub(crate) fn signed_transfer(
+        from: &Keypair,
+        counter: u64,
+        to: AccountId,
+        nanos: u64,
+    ) -> Result<SignedTransfer> {
+        let transfer = Transfer::new(
+            Dot::new(from.public_key(), counter),
+            to,
+            Money::from_nano(nanos),
+            None,
+        )?;
+        let actor_signature = from.sign(&utils::serialise(&transfer));
+        Ok(SignedTransfer {
+            transfer,
+            actor_signature,
+        })
+    }
+
+    /// Returns the validation of `signed_transfer` by the Replica at `index` in `replicas`.
+    pub(crate) fn transfer_validated(
+        signed_transfer: &SignedTransfer,
+        replicas: &SecretKeySet,
+        index: usize,
+    ) -> TransferValidated {
+        let share = replicas
+            .secret_key_share(index)
+            .sign(utils::serialise(signed_transfer));
+        TransferValidated {
+            signed_transfer: signed_transfer.clone(),
+            replica_signature: SignatureShare { index, share },
+            replicas: replicas.public_keys(),
+        }
+    }
+
+    /// Returns the proof that just enough of `replicas` agreed on `signed_transfer`.
+    pub(crate) fn debit_agreement_proof(
+        signed_transfer: &SignedTransfer,
+        replicas: &SecretKeySet,
+    ) -> DebitAgreementProof {
+        let data = utils::serialise(signed_transfer);
+        let shares: Vec<_> = (0..=replicas.threshold())
+            .map(|index| (index, replicas.secret_key_share(index).sign(&data)))
+            .collect();
+        let signature = unwrap!(replicas
+            .public_keys()
+            .combine_signatures(shares.iter().map(|(index, share)| (*index, share))));
+        DebitAgreementProof {
+            signed_transfer: signed_transfer.clone(),
+            debiting_replicas_sig: Signature::Bls(signature),
+            replica_key: replicas.public_keys(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{debit_agreement_proof, signed_transfer, transfer_validated};
+    use super::*;
+    use crate::{utils, Keypair};
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    fn gen_public_key() -> PublicKey {
+        PublicKey::Bls(SecretKey::random().public_key())
+    }
+
+    #[test]
+    fn debit_agreement_proof_verify() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let actor = Keypair::new_ed25519(&mut rng);
+        let replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let mut proof = debit_agreement_proof(
+            &signed_transfer(&actor, 1, gen_public_key(), 1_000)?,
+            &replicas,
+        );
+        proof.verify(&replicas.public_keys())?;
+
+        assert_eq!(proof.amount(), Money::from_nano(1_000));
+        assert_eq!(proof.from(), actor.public_key());
+        assert_eq!(proof.to(), proof.signed_transfer.transfer.to);
+        assert_eq!(proof.transfer_id(), Dot::new(actor.public_key(), 1));
+        assert_eq!(proof.transfer_id(), proof.id());
+        assert_eq!(
+            proof.serialised_size(),
+            utils::serialise(&proof).len() as u64
+        );
+
+        let other_replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        assert_eq!(
+            proof.verify(&other_replicas.public_keys()),
+            Err(Error::InvalidSignature)
+        );
+
+        proof.signed_transfer.transfer.amount = Money::from_nano(1_000_000);
+        assert_eq!(
+            proof.verify(&replicas.public_keys()),
+            Err(Error::InvalidSignature)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_accumulator() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let actor = Keypair::new_ed25519(&mut rng);
+        let signed_transfer = signed_transfer(&actor, 1, gen_public_key(), 1_000)?;
+        let validated = |replicas: &threshold_crypto::SecretKeySet, index: usize| {
+            transfer_validated(&signed_transfer, replicas, index)
+        };
+        let replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let other_replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+
+        let mut accumulator = TransferAccumulator::new(replicas.public_keys());
+        assert_eq!(accumulator.add(validated(&replicas, 0)), None);
+        // Duplicate share.
+        assert_eq!(accumulator.add(validated(&replicas, 0)), None);
+        // Share from another Replica set.
+        assert_eq!(accumulator.add(validated(&other_replicas, 1)), None);
+        // Share not matching the Replica set it claims.
+        let mut forged = validated(&other_replicas, 1);
+        forged.replicas = replicas.public_keys();
+        assert_eq!(accumulator.add(forged), None);
+        assert_eq!(accumulator.pending_count(), 1);
+
+        let proof = unwrap!(accumulator.add(validated(&replicas, 1)));
+        assert_eq!(proof.signed_transfer, signed_transfer);
+        assert_eq!(proof.replica_keys(), replicas.public_keys());
+        proof.verify(&replicas.public_keys())?;
+        assert_eq!(accumulator.pending_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_accumulator_two_of_three() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let actor = Keypair::new_ed25519(&mut rng);
+        let signed_transfer = signed_transfer(&actor, 1, gen_public_key(), 1_000)?;
+        // Threshold 1 means any 2 of the 3 Replicas can produce the proof.
+        let replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let validated = |index| transfer_validated(&signed_transfer, &replicas, index);
+
+        for (first, second) in &[(0, 1), (0, 2), (2, 1)] {
+            let mut accumulator = TransferAccumulator::new(replicas.public_keys());
+            assert_eq!(accumulator.add(validated(*first)), None);
+            let proof = unwrap!(accumulator.add(validated(*second)));
+            assert_eq!(proof.signed_transfer, signed_transfer);
+            proof.verify(&replicas.public_keys())?;
+            assert_eq!(accumulator.pending_count(), 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_accumulator_foreign_share_first() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let actor = Keypair::new_ed25519(&mut rng);
+        let signed_transfer = signed_transfer(&actor, 1, gen_public_key(), 1_000)?;
+        let replicas = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        let mut accumulator = TransferAccumulator::new(replicas.public_keys());
+
+        // Validly signed by a key set the sender made up, and arriving before any real share.
+        let foreign = threshold_crypto::SecretKeySet::random(1, &mut rng);
+        assert_eq!(
+            accumulator.add(transfer_validated(&signed_transfer, &foreign, 0)),
+            None
+        );
+        assert_eq!(accumulator.pending_count(), 0);
+
+        assert_eq!(
+            accumulator.add(transfer_validated(&signed_transfer, &replicas, 0)),
+            None
+        );
+        let proof = unwrap!(accumulator.add(transfer_validated(&signed_transfer, &replicas, 2)));
+        proof.verify(&replicas.public_keys())
+    }
+
+    #[test]
+    fn transfer_memo_round_trip() -> Result<()> {
+        let memo = b"invoice 42".to_vec();
+        let transfer = Transfer::new(
+            Dot::new(gen_public_key(), 1),
+            gen_public_key(),
+            Money::from_nano(1_000),
+            Some(memo.clone()),
+        )?;
+        assert_eq!(transfer.memo(), Some(&memo[..]));
+
+        let decoded: Transfer = unwrap!(bincode::deserialize(&utils::serialise(&transfer)));
+        assert_eq!(decoded, transfer);
+        assert_eq!(decoded.memo(), Some(&memo[..]));
+
+        let no_memo = Transfer::new(transfer.id, transfer.to, transfer.amount, None)?;
+        assert_eq!(no_memo.memo(), None);
+        // The memo is covered by the signature over the transfer.
+        assert_ne!(utils::serialise(&no_memo), utils::serialise(&transfer));
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_memo_oversize() {
+        let new_transfer = |memo_size| {
+            Transfer::new(
+                Dot::new(gen_public_key(), 1),
+                gen_public_key(),
+                Money::from_nano(1_000),
+                Some(vec![0; memo_size]),
+            )
+        };
+        assert!(new_transfer(MAX_MEMO_SIZE_IN_BYTES).is_ok());
+        assert_eq!(
+            new_transfer(MAX_MEMO_SIZE_IN_BYTES + 1),
+            Err(Error::ExceededSize)
+        );
+    }
+
+    #[test]
+    fn transfer_min_amount() -> Result<()> {
+        let below = MIN_TRANSFER.as_nano() - 1;
+        assert_eq!(
+            Transfer::new(
+                Dot::new(gen_public_key(), 1),
+                gen_public_key(),
+                Money::from_nano(below),
+                None,
+            ),
+            Err(Error::AmountBelowMinimum)
+        );
+
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let mut signed = signed_transfer(&keypair, 1, gen_public_key(), MIN_TRANSFER.as_nano())?;
+        signed.verify()?;
+
+        // A transfer built without `Transfer::new` is still rejected by the Replicas.
+        signed.transfer.amount = Money::from_nano(below);
+        signed.actor_signature = keypair.sign(&utils::serialise(&signed.transfer));
+        assert_eq!(signed.verify(), Err(Error::AmountBelowMinimum));
+
+        signed.transfer.amount = MIN_TRANSFER;
+        signed.transfer.to = gen_public_key();
+        assert_eq!(signed.verify(), Err(Error::InvalidSignature));
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_check_against_balance() -> Result<()> {
+        let transfer = Transfer::new(
+            Dot::new(gen_public_key(), 1),
+            gen_public_key(),
+            Money::from_nano(10),
+            None,
+        )?;
+        transfer.check_against_balance(Money::from_nano(10))?;
+        transfer.check_against_balance(Money::from_nano(11))?;
+        assert_eq!(
+            transfer.check_against_balance(Money::from_nano(9)),
+            Err(Error::InsufficientBalance {
+                balance: Money::from_nano(9),
+                required: Money::from_nano(10),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ledger_overspend() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let alice = Keypair::new_ed25519(&mut rng);
+        let bob = Keypair::new_ed25519(&mut rng);
+        let carol = gen_public_key();
+
+        let mut ledger = Ledger::new();
+        ledger.open(alice.public_key(), Money::from_nano(100))?;
+        assert_eq!(
+            ledger.open(alice.public_key(), Money::zero()),
+            Err(Error::BalanceExists)
+        );
+
+        ledger.apply_transfer(&signed_transfer(&alice, 1, bob.public_key(), 60)?)?;
+        ledger.apply_transfer(&signed_transfer(&bob, 1, carol, 20)?)?;
+        assert_eq!(
+            ledger.balance(&alice.public_key()),
+            Some(Money::from_nano(40))
+        );
+        assert_eq!(
+            ledger.balance(&bob.public_key()),
+            Some(Money::from_nano(40))
+        );
+        assert_eq!(ledger.balance(&carol), Some(Money::from_nano(20)));
+
+        // Replaying an applied transfer is a double-spend.
+        assert_eq!(
+            ledger.apply_transfer(&signed_transfer(&alice, 1, carol, 10)?),
+            Err(Error::TransferIdExists)
+        );
+        assert_eq!(
+            ledger.apply_transfer(&signed_transfer(&alice, 3, carol, 10)?),
+            Err(Error::InvalidSuccessor(1))
+        );
+        assert_eq!(
+            ledger.apply_transfer(&signed_transfer(&alice, 2, carol, 41)?),
+            Err(Error::InsufficientBalance {
+                balance: Money::from_nano(40),
+                required: Money::from_nano(41),
+            })
+        );
+        assert_eq!(
+            ledger.apply_transfer(&signed_transfer(
+                &Keypair::new_ed25519(&mut rng),
+                1,
+                carol,
+                1
+            )?),
+            Err(Error::NoSuchSender)
+        );
+        // Rejected transfers leave the ledger unchanged.
+        assert_eq!(
+            ledger.balance(&alice.public_key()),
+            Some(Money::from_nano(40))
+        );
+        assert_eq!(ledger.balance(&carol), Some(Money::from_nano(20)));
+
+        ledger.apply_transfer(&signed_transfer(&alice, 2, carol, 40)?)?;
+        assert_eq!(ledger.balance(&alice.public_key()), Some(Money::zero()));
+        assert_eq!(ledger.balance(&carol), Some(Money::from_nano(60)));
+        Ok(())
+    }
+}
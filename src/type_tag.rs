@@ -0,0 +1,77 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{PoisonError, RwLock},
+};
+
+lazy_static! {
+    static ref REGISTRY: RwLock<BTreeMap<u64, &'static str>> = RwLock::new(BTreeMap::new());
+}
+
+/// The application-defined type of a Map or Sequence, as encoded in its `tag`.
+///
+/// Applications can register a human readable name for their tags, to aid tooling and debugging.
+/// The registry is process-wide and thread-safe.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct TypeTag(pub u64);
+
+impl TypeTag {
+    /// Registers `name` for `tag`, returning the name previously registered for it, if any.
+    pub fn register(tag: u64, name: &'static str) -> Option<&'static str> {
+        REGISTRY
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(tag, name)
+    }
+
+    /// Returns the name registered for this tag, if any.
+    pub fn name(self) -> Option<&'static str> {
+        REGISTRY
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&self.0)
+            .copied()
+    }
+}
+
+impl From<u64> for TypeTag {
+    fn from(tag: u64) -> Self {
+        Self(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapAddress, SequenceAddress, XorName};
+
+    #[test]
+    fn register_and_resolve() {
+        let tag = 43_001;
+        assert_eq!(TypeTag(tag).name(), None);
+        assert_eq!(TypeTag::register(tag, "photo album"), None);
+        assert_eq!(TypeTag(tag).name(), Some("photo album"));
+        assert_eq!(TypeTag::register(tag, "album"), Some("photo album"));
+
+        let name = XorName::random();
+        assert_eq!(MapAddress::Seq { name, tag }.type_name(), Some("album"));
+        assert_eq!(
+            SequenceAddress::Public { name, tag }.type_name(),
+            Some("album")
+        );
+        assert_eq!(
+            SequenceAddress::Public { name, tag: 43_002 }.type_name(),
+            None
+        );
+    }
+}
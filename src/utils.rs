@@ -7,9 +7,10 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::{Error, Message, MessageId, PublicKey, Result, Signature};
+use crate::{Error, Message, MessageId, PublicKey, Result, Signature, XorName};
 use multibase::{self, Base, Decodable};
 use serde::{de::DeserializeOwned, Serialize};
+use subtle::ConstantTimeEq;
 use unwrap::unwrap;
 
 /// Verify that a signature is valid for a given `Request` + `MessageId` combination.
@@ -28,6 +29,24 @@ pub(crate) fn serialise<T: Serialize>(data: &T) -> Vec<u8> {
     unwrap!(bincode::serialize(data))
 }
 
+/// Wrapper for raw bincode::deserialize.
+pub(crate) fn deserialise<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    bincode::deserialize(bytes).map_err(|e| Error::FailedToParse(e.to_string()))
+}
+
+/// Compares the serialised forms of `lhs` and `rhs` in constant time.
+pub(crate) fn ct_eq_serialised<T: Serialize>(lhs: &T, rhs: &T) -> bool {
+    serialise(lhs)
+        .as_slice()
+        .ct_eq(serialise(rhs).as_slice())
+        .into()
+}
+
+/// Derives a data name from the owner and a nonce, by hashing both.
+pub(crate) fn derive_name(owner: &PublicKey, nonce: &[u8]) -> XorName {
+    XorName(tiny_keccak::sha3_256(&serialise(&(owner, nonce))))
+}
+
 /// Wrapper for z-Base-32 multibase::encode.
 pub(crate) fn encode<T: Serialize>(data: &T) -> String {
     let serialised = serialise(&data);